@@ -0,0 +1,73 @@
+//! Append-only audit log of dispatches.
+//!
+//! When `log_file` is configured (or `GH_DISPATCH_LOG_FILE` is set), one JSON
+//! line is appended per dispatched ref once its outcome is known.  The file is
+//! reopened for every record, so it can be rotated while the tool runs.
+//!
+//! The log is also where `--prefill-from-run` finds a past run's inputs, as
+//! GitHub doesn't expose the inputs a run was dispatched with.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs::{OpenOptions, read_to_string};
+use std::io::Write;
+use std::path::Path;
+
+/// One dispatch, as written to the audit log.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a> {
+    pub timestamp: DateTime<Utc>,
+    /// Login of the user whose token dispatched the workflow
+    pub user: &'a str,
+    pub app: &'a str,
+    pub workflow: &'a str,
+    /// Repository as `owner/repo`
+    pub repo: String,
+    #[serde(rename = "ref")]
+    pub git_ref: &'a str,
+    /// Inputs sent, with secret values masked
+    pub inputs: &'a IndexMap<String, String>,
+    pub run_id: Option<u64>,
+    pub html_url: Option<String>,
+    /// `None` when the run wasn't watched to completion
+    pub conclusion: Option<&'a str>,
+}
+
+/// Append a record to the audit log as a single JSON line.
+pub fn append_record(path: &Path, record: &AuditRecord<'_>) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    // One write per line so concurrent appenders can't interleave records
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write audit log {}", path.display()))
+}
+
+/// A past dispatch read back from the audit log.
+#[derive(Debug, Deserialize)]
+pub struct LoggedRun {
+    pub app: String,
+    /// Workflow file, e.g. `deploy.yml`
+    pub workflow: String,
+    /// Inputs sent, with secret values masked
+    pub inputs: IndexMap<String, String>,
+    run_id: Option<u64>,
+}
+
+/// Find the latest record of a run, for the inputs it was dispatched with.
+///
+/// Secret values are returned masked, as they were logged.
+pub fn find_run(path: &Path, run_id: u64) -> Result<LoggedRun> {
+    let log = read_to_string(path)
+        .with_context(|| format!("Failed to read audit log {}", path.display()))?;
+    log.lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<LoggedRun>(line).ok())
+        .find(|run| run.run_id == Some(run_id))
+        .with_context(|| format!("Run {run_id} not found in audit log {}", path.display()))
+}
@@ -0,0 +1,191 @@
+//! The `check` subcommand.
+//!
+//! Validates the config for CI: authenticates, then confirms every configured
+//! workflow exists, has a `workflow_dispatch` trigger, and accepts the inputs
+//! the config sets.  Nothing is dispatched.  Any problem makes the command
+//! fail, so config drift is caught before someone tries to deploy.
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use futures::future::join_all;
+use octocrab::Octocrab;
+use serde::Serialize;
+
+use crate::cli::OutputFormat;
+use crate::config::{Config, WorkflowRef, resolve_chain, resolve_pipeline};
+use crate::github::{
+    create_client_for, get_current_login, get_workflow_schema, read_workflow_schema, ref_exists,
+};
+use crate::ui::{create_spinner, describe, icons};
+
+/// Result of checking the whole config.
+#[derive(Debug, Serialize)]
+struct CheckReport {
+    ok: bool,
+    /// Login of the global token's user, if it authenticated
+    login: Option<String>,
+    /// Problems not tied to one workflow (authentication, pipelines)
+    problems: Vec<String>,
+    workflows: Vec<CheckedWorkflow>,
+}
+
+/// Result of checking one configured workflow.
+#[derive(Debug, Serialize)]
+struct CheckedWorkflow {
+    app: String,
+    workflow: String,
+    /// Repository as `owner/repo`
+    repo: String,
+    file: String,
+    ok: bool,
+    problems: Vec<String>,
+}
+
+/// Check every app and workflow in `config`, printing a report in `format`.
+///
+/// Workflows are checked concurrently.  Returns an error if anything failed.
+pub async fn check(client: &Octocrab, config: &Config, format: OutputFormat) -> Result<()> {
+    let spinner = (format != OutputFormat::Json).then(|| create_spinner("Checking config..."));
+
+    let mut problems = Vec::new();
+    let login = match get_current_login(client).await {
+        Ok(login) => Some(login),
+        Err(err) => {
+            problems.push(format!("Authentication failed: {}", err.root_cause()));
+            None
+        }
+    };
+    for (app_name, app) in &config.apps {
+        for pipeline in app.pipelines.keys() {
+            if let Err(err) = resolve_pipeline(app, pipeline) {
+                problems.push(format!("App '{app_name}': {err:#}"));
+            }
+        }
+    }
+
+    let workflows = join_all(config.apps.iter().flat_map(|(app_name, app)| {
+        app.workflows
+            .iter()
+            .map(move |(key, workflow_ref)| async move {
+                let mut problems = match resolve_chain(app, key) {
+                    Ok(_) => Vec::new(),
+                    Err(err) => vec![format!("{err:#}")],
+                };
+                problems.extend(check_workflow(client, workflow_ref).await);
+                CheckedWorkflow {
+                    app: app_name.clone(),
+                    workflow: key.clone(),
+                    repo: format!("{}/{}", workflow_ref.owner, workflow_ref.repo),
+                    file: workflow_ref.workflow.clone(),
+                    ok: problems.is_empty(),
+                    problems,
+                }
+            })
+    }))
+    .await;
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+
+    let failed = problems.len() + workflows.iter().map(|wf| wf.problems.len()).sum::<usize>();
+    let report = CheckReport {
+        ok: failed == 0,
+        login,
+        problems,
+        workflows,
+    };
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if failed > 0 {
+        bail!("Config check found {failed} problem(s)");
+    }
+    Ok(())
+}
+
+/// Check that one workflow can be dispatched as configured.
+///
+/// Returns the problems found; empty when the workflow is fine.
+async fn check_workflow(client: &Octocrab, workflow_ref: &WorkflowRef) -> Vec<String> {
+    let WorkflowRef { owner, repo, .. } = workflow_ref;
+
+    // A workflow with its own token is checked with that token
+    let own_client;
+    let client = match &workflow_ref.token {
+        Some(source) => match create_client_for(source) {
+            Ok(c) => {
+                own_client = c;
+                &own_client
+            }
+            Err(err) => return vec![format!("{err:#}")],
+        },
+        None => client,
+    };
+
+    let schema = match &workflow_ref.schema_file {
+        Some(path) => read_workflow_schema(path),
+        None => get_workflow_schema(client, owner, repo, &workflow_ref.workflow, false).await,
+    };
+    let schema = match schema {
+        Ok(schema) => schema,
+        Err(err) => return vec![describe(&err)],
+    };
+
+    let mut problems = Vec::new();
+    if !schema.dispatchable {
+        problems.push("No workflow_dispatch trigger".to_string());
+    }
+    for skipped in &schema.skipped {
+        problems.push(format!("Malformed input {skipped}"));
+    }
+    let configured = workflow_ref.inputs.iter().flat_map(|inputs| inputs.keys());
+    let configured = configured
+        .chain(workflow_ref.input_commands.keys())
+        .chain(workflow_ref.inputs_from.keys());
+    for name in configured {
+        if !schema.inputs.contains_key(name) {
+            problems.push(format!("Configured input '{name}' is not in the workflow"));
+        }
+    }
+    if let Some(git_ref) = &workflow_ref.git_ref {
+        match ref_exists(client, owner, repo, git_ref).await {
+            Ok(true) => {}
+            Ok(false) => problems.push(format!("Ref '{git_ref}' not found")),
+            Err(err) => problems.push(format!(
+                "Could not check ref '{git_ref}': {}",
+                describe(&err)
+            )),
+        }
+    }
+    problems
+}
+
+/// Print the report for people: a line per workflow, then its problems.
+fn print_report(report: &CheckReport) {
+    let icons = icons();
+    match &report.login {
+        Some(login) => println!("{} Authenticated as {login}", icons.success.green().bold()),
+        None => println!("{} Not authenticated", icons.failure.red().bold()),
+    }
+    for problem in &report.problems {
+        println!("  {}", problem.red());
+    }
+    for wf in &report.workflows {
+        let icon = if wf.ok {
+            icons.success.green().bold()
+        } else {
+            icons.failure.red().bold()
+        };
+        println!(
+            "{icon} {} {}",
+            format!("{}/{}", wf.app, wf.workflow).bold(),
+            format!("({} {})", wf.repo, wf.file).dimmed()
+        );
+        for problem in &wf.problems {
+            println!("  {}", problem.red());
+        }
+    }
+}
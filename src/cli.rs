@@ -2,7 +2,12 @@
 //!
 //! Defines the command-line interface using clap.
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::Duration;
 
 // -----------------------------------------------------------------------------
 // Types
@@ -13,15 +18,343 @@ use clap::Parser;
 #[command(name = "gh-dispatch")]
 #[command(about = "A CLI tool for triggering GitHub Actions workflows with polling support.")]
 #[command(version)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Application name from config
     pub app: Option<String>,
 
+    /// Dispatch the workflow for every app whose name matches this pattern
+    /// (`*` and `?` wildcards, e.g. 'frontend-*'), watching the runs together
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        requires = "workflow",
+        conflicts_with_all = ["app", "pipeline", "repo_from_git", "loop_interval"]
+    )]
+    pub app_glob: Option<String>,
+
+    /// Pipeline to run from the app's config (e.g., release)
+    #[arg(conflicts_with = "workflow")]
+    pub pipeline: Option<String>,
+
     /// Workflow to run (e.g., build, deploy, test)
     #[arg(short, long)]
     pub workflow: Option<String>,
 
+    /// Dispatch the workflow file given with -w (e.g. deploy.yml) in the
+    /// repository of the current git checkout's origin remote; any config
+    /// (including GH_DISPATCH_CONFIG) is ignored
+    #[arg(long, requires = "workflow", conflicts_with_all = ["app", "pipeline"])]
+    pub repo_from_git: bool,
+
+    /// Config file path or https:// URL, instead of ./config.toml or
+    /// ~/.config/gh-dispatch/config.toml
+    #[arg(long, value_name = "PATH|URL", env = "GH_DISPATCH_CONFIG")]
+    pub config: Option<String>,
+
+    /// Git ref to dispatch on, overriding the config; repeat to dispatch once per ref
+    #[arg(long = "ref", value_name = "REF")]
+    pub git_ref: Vec<String>,
+
+    /// With several --ref values or --app-glob, how many runs to look up and
+    /// watch at once
+    #[arg(long, value_name = "N", default_value = "4")]
+    pub concurrency: NonZeroUsize,
+
+    /// Don't check that the git ref exists before prompting for inputs
+    #[arg(long)]
+    pub skip_ref_check: bool,
+
+    /// Before confirming, warn if the ref is behind the repository's default
+    /// branch (also set by the config's `check_behind`)
+    #[arg(long)]
+    pub check_behind: bool,
+
+    /// Before confirming, warn if the ref has moved on from this commit,
+    /// e.g. the one last reviewed
+    #[arg(long, value_name = "SHA")]
+    pub since_commit: Option<String>,
+
+    /// Inputs as a JSON object, e.g. '{"version":"1.2","dry_run":true}'.
+    /// Overrides config values; applies to the workflow being dispatched
+    /// (the last stage of a pipeline)
+    #[arg(long, value_name = "JSON", value_parser = parse_json_object)]
+    pub input_json: Option<Map<String, Value>>,
+
+    /// Prefill inputs with those a previous run was dispatched with, as
+    /// recorded in the audit log (`log_file`); only the log is consulted, as
+    /// GitHub doesn't keep a run's inputs.  The app and workflow default to
+    /// the run's; other input options take precedence
+    #[arg(long, value_name = "RUN_ID")]
+    pub prefill_from_run: Option<u64>,
+
+    /// Read inputs from stdin, one `key=value` per line (blank lines and
+    /// `#` comments are ignored); --input-json values take precedence
+    #[arg(long)]
+    pub input_stdin: bool,
+
+    /// Review and edit all resolved inputs in $EDITOR before dispatching
+    #[arg(long)]
+    pub input_editor: bool,
+
+    /// Read the workflow's inputs from this local file instead of GitHub
+    #[arg(long, value_name = "PATH")]
+    pub schema_file: Option<PathBuf>,
+
+    /// Re-request the workflow file and default branch from GitHub, bypassing
+    /// response caches (slower, but sees a just-pushed workflow change)
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// Don't fetch the workflow file: send the configured and --input-json
+    /// inputs as-is, without prompting or validating them.  Fails when no
+    /// inputs are given; pass --input-json '{}' for a workflow without inputs
+    #[arg(long, conflicts_with_all = ["schema_file", "input_editor"])]
+    pub no_schema: bool,
+
+    /// Mask every input value when printing the inputs
+    #[arg(long)]
+    pub hide_inputs: bool,
+
+    /// Answer the confirmation prompt automatically after this many seconds
+    /// (at once when stdin is not a terminal)
+    #[arg(long, value_name = "SECONDS")]
+    pub confirm_timeout: Option<u64>,
+
+    /// What to do when --confirm-timeout runs out
+    #[arg(long, value_enum, default_value_t = TimeoutAction::Proceed, requires = "confirm_timeout")]
+    pub on_confirm_timeout: TimeoutAction,
+
+    /// Resolve and show the inputs, but don't dispatch
+    ///
+    /// With a `schema_file` and a `ref`, this needs no network access.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print the equivalent `curl` command for each dispatch instead of
+    /// dispatching (secret inputs are read from `INPUT_<NAME>` variables)
+    #[arg(long)]
+    pub print_curl: bool,
+
     /// Don't wait for workflow to complete
     #[arg(long)]
     pub no_wait: bool,
+
+    /// Dispatch repeatedly at this interval (e.g. 30s, 5m, 1h) until Ctrl-C
+    #[arg(long = "loop", value_name = "INTERVAL", value_parser = parse_interval)]
+    pub loop_interval: Option<Duration>,
+
+    /// With --loop, stop at the first failed run instead of continuing
+    #[arg(long, requires = "loop_interval")]
+    pub stop_on_failure: bool,
+
+    /// After a successful run, keep watching runs it triggers via `workflow_run`
+    #[arg(long, conflicts_with = "no_wait")]
+    pub follow_triggered: bool,
+
+    /// Print each job's log output when the job completes
+    #[arg(long, conflicts_with = "no_wait")]
+    pub watch_logs: bool,
+
+    /// Stop watching once this job completes, exiting with its conclusion
+    /// (the rest of the run keeps going)
+    #[arg(long, value_name = "JOB", conflicts_with_all = ["no_wait", "follow_triggered"])]
+    pub wait_for: Option<String>,
+
+    /// Show only job counts, not a line per job, for runs with more jobs
+    /// than this
+    #[arg(long, value_name = "N")]
+    pub max_jobs: Option<usize>,
+
+    /// What to do if the run is still going after 30 minutes of watching:
+    /// fail, cancel the run and fail, or stop watching and exit 0
+    #[arg(long, value_enum, default_value_t = WaitTimeoutAction::Fail)]
+    pub wait_timeout_action: WaitTimeoutAction,
+
+    /// Stop watching and fail as soon as any job fails, leaving the rest of
+    /// the run going
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Poll less often (up to every 30s) while a watched run is idle, e.g.
+    /// queued, and every 5s again once it changes
+    #[arg(long)]
+    pub watch_interval_adaptive: bool,
+
+    /// Which completed steps to print while watching
+    #[arg(long, value_enum, default_value_t = StepFilter::All)]
+    pub steps: StepFilter,
+
+    /// Use ASCII status icons instead of Unicode (the default when the
+    /// locale isn't UTF-8)
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// While watching, print newline-delimited JSON events to stdout instead
+    /// of the progress display
+    #[arg(long, conflicts_with_all = ["no_wait", "watch_logs", "format"])]
+    pub json_events: bool,
+
+    /// Which annotation levels to print while watching; overrides the
+    /// config's `annotations` [default: all]
+    #[arg(long, value_enum, value_name = "LEVELS")]
+    pub annotations: Option<AnnotationFilter>,
+
+    /// Run conclusion that makes the command fail; repeatable, overrides the
+    /// config's `fail_on` [default: failure, timed_out]
+    #[arg(long, value_name = "CONCLUSION")]
+    pub fail_on: Vec<String>,
+
+    /// Write a JUnit XML report of each watched run to this file, one test
+    /// case per job
+    #[arg(long, value_name = "PATH", conflicts_with = "no_wait")]
+    pub output_junit: Option<PathBuf>,
+
+    /// Write a Markdown summary of each watched run to this file: its jobs
+    /// and their annotations, ready to paste into a PR comment or ticket
+    #[arg(long, value_name = "PATH", conflicts_with = "no_wait")]
+    pub output_markdown: Option<PathBuf>,
+
+    /// How to render the inputs and the final result
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub format: OutputFormat,
+}
+
+/// Answer given to the confirmation prompt when `--confirm-timeout` expires.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeoutAction {
+    Proceed,
+    Abort,
+}
+
+/// What the watcher does when a run outlasts its time limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum WaitTimeoutAction {
+    /// Exit with an error, leaving the run going
+    #[default]
+    Fail,
+    /// Cancel the run, then exit with an error
+    Cancel,
+    /// Stop watching and exit successfully, leaving the run going
+    Continue,
+}
+
+/// Which completed steps the watcher prints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum StepFilter {
+    /// Every step as it completes
+    #[default]
+    All,
+    /// Only steps that failed or timed out
+    Failed,
+    /// No steps; only the job lines
+    None,
+}
+
+/// Which annotation levels the watcher prints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationFilter {
+    /// Notices, warnings and errors
+    #[default]
+    All,
+    /// Warnings and errors
+    Warnings,
+    /// Only errors (`failure` level)
+    Errors,
+    /// No annotations
+    None,
+}
+
+impl AnnotationFilter {
+    /// Whether an annotation of `level` ("notice", "warning" or "failure")
+    /// passes the filter.
+    pub fn shows(self, level: &str) -> bool {
+        match self {
+            AnnotationFilter::All => true,
+            AnnotationFilter::Warnings => level != "notice",
+            AnnotationFilter::Errors => level == "failure",
+            AnnotationFilter::None => false,
+        }
+    }
+}
+
+/// Subcommands besides the default dispatch flow.
+#[derive(Subcommand)]
+pub enum Command {
+    /// List a repository's workflows and whether they can be dispatched
+    Discover {
+        /// Repository to inspect, as owner/repo
+        repo: String,
+
+        /// Print a config entry for this workflow file (e.g. deploy.yml)
+        #[arg(long, value_name = "WORKFLOW")]
+        snippet: Option<String>,
+
+        /// How to render the list
+        #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+        format: OutputFormat,
+    },
+    /// Check, without dispatching, that every configured workflow exists, is
+    /// dispatchable and accepts its configured inputs; fails on any problem
+    Check {
+        /// Config file path or https:// URL, as for the main command
+        #[arg(long, value_name = "PATH|URL", env = "GH_DISPATCH_CONFIG")]
+        config: Option<String>,
+
+        /// How to render the report
+        #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+        format: OutputFormat,
+    },
+    /// Show the version, config file, and token source in use, for bug reports
+    Info {
+        /// Config file path or https:// URL, as for the main command
+        #[arg(long, value_name = "PATH|URL", env = "GH_DISPATCH_CONFIG")]
+        config: Option<String>,
+    },
+}
+
+/// Output style for the inputs summary and the final result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable `key = value` lines
+    Plain,
+    /// Keys and values aligned in columns
+    Table,
+    /// Machine-readable JSON on stdout
+    Json,
+}
+
+// -----------------------------------------------------------------------------
+// Parsers
+// -----------------------------------------------------------------------------
+
+/// Parse a `--input-json` argument, which must be a JSON object.
+fn parse_json_object(s: &str) -> Result<Map<String, Value>, String> {
+    match serde_json::from_str(s) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err("expected a JSON object".to_string()),
+        Err(e) => Err(format!("invalid JSON: {e}")),
+    }
+}
+
+/// Parse a `--loop` interval: a number with an optional `s`, `m`, or `h` unit
+/// (seconds when omitted).
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1),
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 60 * 60),
+        _ => (s, 1),
+    };
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid interval '{s}', expected e.g. 30s, 5m, 1h"))?;
+    if value == 0 {
+        return Err("interval must be greater than zero".to_string());
+    }
+    Ok(Duration::from_secs(value * multiplier))
 }
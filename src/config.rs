@@ -1,20 +1,64 @@
 //! Configuration loading and types.
 //!
-//! Loads config from `./config.toml` or `~/.config/gh-dispatch/config.toml`.
+//! Loads config from `./config.toml` or `~/.config/gh-dispatch/config.toml`,
+//! or from a path or `https://` URL given with `--config`.  Remote configs
+//! are cached under `~/.cache/gh-dispatch` and reused for an hour, or longer
+//! if they can't be fetched.
+//!
+//! A local config can pull apps in from other files with `include`, a list
+//! of paths relative to it that may use `*` and `?` wildcards.
 //!
 //! # Example config.toml
 //!
 //! ```toml
+//! include = ["teams/*.toml"]
+//! fail_on = ["failure", "timed_out", "cancelled"]
+//! annotations = "warnings"
+//! log_file = "/var/log/gh-dispatch.jsonl"
+//! check_behind = true
+//! default_owner = "owner"
+//! pre_dispatch = "./scripts/check-branch.sh"
+//!
+//! [http]
+//! proxy = "http://proxy.corp:3128"
+//! headers = { X-Corp-Client = "gh-dispatch" }
+//!
 //! [apps.my-app]
 //! build = { repo = "owner/repo", workflow = "build.yml", ref = "develop", inputs = { app = "my-app" } }
 //! deploy = { repo = "owner/repo", workflow = "deploy.yml" }
-//! test = { repo = "owner/repo", workflow = "test.yml" }
+//! test = { repo = "repo", workflow = "test.yml" }  # owner from default_owner
+//! release = { repo = "other-org/repo", workflow = "release.yml", token_env = "OTHER_ORG_TOKEN" }
+//! deploy-tagged = { repo = "owner/repo", workflow = "deploy.yml", inputs_from = { tag = "build.image_tag" } }
+//! deploy-release = { repo = "owner/repo", workflow = "deploy.yml", inputs = { version = "{{env.release}}" } }
+//! migrate = { repo = "owner/repo", workflow = "migrate.yml", inputs = { targets = ["eu", "us"], options = { dry_run = true } } }
+//! smoke-test = { repo = "owner/repo", workflow = "smoke.yml", confirm = false }
+//! deploy-prod = { repo = "owner/repo", workflow = "deploy.yml", ref = "main", confirm = "twice" }
+//! seed = { repo = "owner/repo", workflow = "seed.yml", inputs = { db_password = { command = "op read op://vault/db/password" } } }
+//!
+//! [apps.my-app.env]
+//! release = "2.4"
+//!
+//! [apps.my-app.pipelines.release]
+//! stages = ["build", "test", "deploy"]
 //! ```
 
 use anyhow::{Context, Result, bail};
+use http::header::{HeaderName, HeaderValue};
 use indexmap::IndexMap;
 use serde::Deserialize;
-use std::{fs::read_to_string, path::PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use crate::cli::AnnotationFilter;
+use crate::github::fetch_text;
+use crate::proxy::Proxy;
+
+const REMOTE_CONFIG_TTL: u64 = 60 * 60; // 1 hour
 
 // -----------------------------------------------------------------------------
 // Types
@@ -23,12 +67,227 @@ use std::{fs::read_to_string, path::PathBuf};
 /// Top-level config structure.
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    /// Other config files whose apps are merged in (local configs only)
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// What to do when an included file defines an app that's already defined
+    #[serde(default)]
+    pub include_conflict: IncludeConflict,
+    /// Run conclusions that make the command exit nonzero
+    #[serde(default = "default_fail_on")]
+    pub fail_on: Vec<String>,
+    /// Annotation levels printed while watching, unless `--annotations` is given
+    #[serde(default)]
+    pub annotations: AnnotationFilter,
+    /// Audit log to append a JSON line to per dispatch
+    #[serde(default)]
+    pub log_file: Option<PathBuf>,
+    /// Warn before confirming when the ref is behind the default branch
+    #[serde(default)]
+    pub check_behind: bool,
+    /// Owner for `repo` values without one; `GH_DISPATCH_OWNER` takes precedence
+    #[serde(default)]
+    pub default_owner: Option<String>,
+    /// Command run before each dispatch; a nonzero exit aborts it
+    #[serde(default)]
+    pub pre_dispatch: Option<String>,
+    /// Command run after each dispatch, once its outcome is known
+    #[serde(default)]
+    pub post_dispatch: Option<String>,
+    /// Glyph overrides for the terminal UI
+    #[serde(default)]
+    pub ui: UiConfig,
+    /// Proxy and extra headers for GitHub API requests
+    #[serde(default)]
+    pub http: HttpConfig,
     /// Map of application name to its configuration
+    #[serde(default)]
     pub apps: IndexMap<String, AppConfig>,
+    /// Problems met while loading, to warn about once output is set up
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+/// How `include` handles an app defined in more than one file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IncludeConflict {
+    /// The later definition replaces the earlier one
+    #[default]
+    Override,
+    /// Loading the config fails
+    Error,
+}
+
+/// An included config file: only apps.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IncludedConfig {
+    #[serde(default)]
+    apps: IndexMap<String, AppConfig>,
+}
+
+/// The `[ui]` table: replacements for individual status glyphs.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UiConfig {
+    pub success: Option<String>,
+    pub failure: Option<String>,
+    pub neutral: Option<String>,
+    pub running: Option<String>,
+    pub unknown: Option<String>,
+    pub info: Option<String>,
+    pub warning: Option<String>,
+    pub separator: Option<String>,
+    pub gutter: Option<String>,
+}
+
+/// The `[http]` table: how to reach the GitHub API.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpConfig {
+    /// HTTP proxy URL, overriding `HTTPS_PROXY`/`HTTP_PROXY`
+    pub proxy: Option<String>,
+    /// Headers sent with every request
+    #[serde(default)]
+    pub headers: IndexMap<String, String>,
+}
+
+impl HttpConfig {
+    /// Whether the table sets anything.
+    pub fn is_empty(&self) -> bool {
+        self.proxy.is_none() && self.headers.is_empty()
+    }
+}
+
+impl Config {
+    /// Config for dispatching one workflow file of a repository directly,
+    /// without a config file.  The app is named `owner/repo` and the workflow
+    /// is keyed by its filename.
+    pub fn for_workflow(owner: &str, repo: &str, workflow: &str) -> Result<Self> {
+        let workflow = workflow_file_name(workflow).map_err(anyhow::Error::msg)?;
+        let workflow_ref = WorkflowRef {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            workflow: workflow.clone(),
+            git_ref: None,
+            inputs: None,
+            inputs_from: IndexMap::new(),
+            token: None,
+            schema_file: None,
+            label: None,
+            secret_inputs: Vec::new(),
+            input_commands: IndexMap::new(),
+            input_order: Vec::new(),
+            pre_dispatch: None,
+            post_dispatch: None,
+            confirm: Confirmation::default(),
+        };
+        let app = AppConfig {
+            pipelines: IndexMap::new(),
+            env: IndexMap::new(),
+            workflows: IndexMap::from([(workflow, workflow_ref)]),
+        };
+        Ok(Config {
+            include: Vec::new(),
+            include_conflict: IncludeConflict::default(),
+            fail_on: default_fail_on(),
+            annotations: AnnotationFilter::default(),
+            log_file: None,
+            check_behind: false,
+            default_owner: None,
+            pre_dispatch: None,
+            post_dispatch: None,
+            ui: UiConfig::default(),
+            http: HttpConfig::default(),
+            apps: IndexMap::from([(format!("{owner}/{repo}"), app)]),
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Names of the apps matching a `*`/`?` wildcard pattern, sorted.
+    pub fn apps_matching(&self, pattern: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .apps
+            .keys()
+            .map(String::as_str)
+            .filter(|name| wildcard_match(pattern, name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Conclusions treated as failures unless configured otherwise.
+fn default_fail_on() -> Vec<String> {
+    vec!["failure".to_string(), "timed_out".to_string()]
+}
+
+/// Configuration for a single application.
+///
+/// Every key of an app's table but `pipelines` and `env` names a workflow.
+#[derive(Debug)]
+pub struct AppConfig {
+    /// Named pipelines of workflows run in sequence
+    pub pipelines: IndexMap<String, Pipeline>,
+    /// Named values that input values can reference as `{{env.name}}`
+    pub env: IndexMap<String, String>,
+    /// Map of workflow name to its reference
+    pub workflows: IndexMap<String, WorkflowRef>,
 }
 
-/// Configuration for a single application: map of workflow name to its reference.
-pub type AppConfig = IndexMap<String, WorkflowRef>;
+// Deserialized by hand rather than with a flattened `workflows`, which would
+// take a workflow named `pipelines` or `env` for those tables and hide why a
+// workflow failed to parse behind a generic "data did not match" error.
+impl<'de> Deserialize<'de> for AppConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let table = IndexMap::<String, toml::Value>::deserialize(deserializer)?;
+        let mut app = AppConfig {
+            pipelines: IndexMap::new(),
+            env: IndexMap::new(),
+            workflows: IndexMap::new(),
+        };
+        for (key, value) in table {
+            let is_workflow = value
+                .get("workflow")
+                .is_some_and(|workflow| workflow.is_str());
+            match key.as_str() {
+                "pipelines" | "env" if is_workflow => {
+                    return Err(D::Error::custom(format!(
+                        "'{key}' is reserved for the app's {key} table and can't name a \
+                         workflow; use another key (and `label` to keep the name shown)"
+                    )));
+                }
+                "pipelines" => {
+                    app.pipelines = value
+                        .try_into()
+                        .map_err(|err| D::Error::custom(format!("Invalid pipelines: {err}")))?;
+                }
+                "env" => {
+                    app.env = value
+                        .try_into()
+                        .map_err(|err| D::Error::custom(format!("Invalid env: {err}")))?;
+                }
+                _ => {
+                    let workflow = value
+                        .try_into()
+                        .map_err(|err| D::Error::custom(format!("Workflow '{key}': {err}")))?;
+                    app.workflows.insert(key, workflow);
+                }
+            }
+        }
+        Ok(app)
+    }
+}
+
+/// An ordered sequence of workflows, run one after another.
+#[derive(Debug, Deserialize)]
+pub struct Pipeline {
+    /// Workflow names in the order they run
+    pub stages: Vec<String>,
+}
 
 /// Reference to a GitHub Actions workflow.
 #[derive(Debug, Deserialize)]
@@ -38,12 +297,82 @@ pub struct WorkflowRef {
     pub owner: String,
     /// Repository name
     pub repo: String,
-    /// Workflow filename (e.g., "build.yml")
+    /// Workflow filename (e.g., "build.yml"); a configured
+    /// `.github/workflows/build.yml` path is reduced to the filename
     pub workflow: String,
     /// Git ref to dispatch on (branch or tag). Defaults to the repo's default branch.
     pub git_ref: Option<String>,
     /// Optional pre-filled input values (skip prompts for these)
     pub inputs: Option<IndexMap<String, String>>,
+    /// Inputs filled from the outputs of other workflows run first in the same invocation
+    pub inputs_from: IndexMap<String, OutputRef>,
+    /// Token for this workflow's repository, overriding the global token
+    pub token: Option<TokenSource>,
+    /// Local copy of the workflow file to read inputs from instead of GitHub
+    pub schema_file: Option<PathBuf>,
+    /// Friendly name shown in prompts instead of the config key
+    pub label: Option<String>,
+    /// Inputs whose values are masked when the inputs are printed; includes
+    /// every input in `input_commands`
+    pub secret_inputs: Vec<String>,
+    /// Inputs whose values are printed by a shell command, run at dispatch
+    /// time (`inputs = { name = { command = "..." } }`)
+    pub input_commands: IndexMap<String, String>,
+    /// Inputs to prompt for first, in this order
+    pub input_order: Vec<String>,
+    /// Command run before each dispatch, instead of the global `pre_dispatch`
+    pub pre_dispatch: Option<String>,
+    /// Command run after each dispatch, instead of the global `post_dispatch`
+    pub post_dispatch: Option<String>,
+    /// How the dispatch is confirmed before it's sent
+    pub confirm: Confirmation,
+}
+
+impl WorkflowRef {
+    /// Name to show for this workflow: its `label`, or else its config key.
+    pub fn display_name<'a>(&'a self, key: &'a str) -> &'a str {
+        self.label.as_deref().unwrap_or(key)
+    }
+}
+
+/// How a workflow's dispatch is confirmed: `confirm = false`, `true` (the
+/// default) or `"twice"`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Dispatch without asking
+    Skip,
+    /// Ask "Continue?"
+    #[default]
+    Once,
+    /// Ask "Continue?", then have the app's name typed in
+    Twice,
+}
+
+/// Where to read a GitHub token for a specific workflow's repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TokenSource {
+    /// Name of an environment variable holding the token (`token_env`)
+    Env(String),
+    /// Shell command that prints the token (`token_command`)
+    Command(String),
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Env(var) => write!(f, "${var}"),
+            TokenSource::Command(command) => write!(f, "`{command}`"),
+        }
+    }
+}
+
+/// Reference to a named output of another workflow in the same app (`"build.image_tag"`).
+#[derive(Debug, Clone)]
+pub struct OutputRef {
+    /// Key of the upstream workflow within the app
+    pub workflow: String,
+    /// Name of the output captured from the upstream run
+    pub output: String,
 }
 
 /// Raw deserialization struct for `WorkflowRef`.
@@ -54,25 +383,113 @@ struct WorkflowRefRaw {
     #[serde(rename = "ref", default)]
     git_ref: Option<String>,
     #[serde(default)]
-    inputs: Option<IndexMap<String, String>>,
+    inputs: Option<IndexMap<String, toml::Value>>,
+    #[serde(default)]
+    inputs_from: IndexMap<String, String>,
+    #[serde(default)]
+    token_env: Option<String>,
+    #[serde(default)]
+    token_command: Option<String>,
+    #[serde(default)]
+    schema_file: Option<PathBuf>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    secret_inputs: Vec<String>,
+    #[serde(default)]
+    input_order: Vec<String>,
+    #[serde(default)]
+    pre_dispatch: Option<String>,
+    #[serde(default)]
+    post_dispatch: Option<String>,
+    #[serde(default)]
+    confirm: Option<toml::Value>,
 }
 
 impl TryFrom<WorkflowRefRaw> for WorkflowRef {
     type Error = String;
 
     fn try_from(raw: WorkflowRefRaw) -> Result<Self, Self::Error> {
-        let (owner, repo) = raw
-            .repo
-            .split_once('/')
-            .map(|(o, r)| (o.to_string(), r.to_string()))
-            .ok_or_else(|| format!("Invalid repo format '{}', expected 'owner/repo'", raw.repo))?;
+        // A bare repo name leaves the owner empty, to be filled from the
+        // default owner once the whole config is loaded
+        let (owner, repo) = match raw.repo.split_once('/') {
+            Some((o, r)) => (o.to_string(), r.to_string()),
+            None if !raw.repo.is_empty() => (String::new(), raw.repo),
+            None => return Err("Empty repo, expected 'owner/repo' or 'repo'".to_string()),
+        };
+
+        let inputs_from = raw
+            .inputs_from
+            .into_iter()
+            .map(|(input, source)| {
+                let (workflow, output) = source.split_once('.').ok_or_else(|| {
+                    format!("Invalid output reference '{source}', expected 'workflow.output'")
+                })?;
+                let output_ref = OutputRef {
+                    workflow: workflow.to_string(),
+                    output: output.to_string(),
+                };
+                Ok((input, output_ref))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let workflow = workflow_file_name(&raw.workflow)?;
+
+        let token = match (raw.token_env, raw.token_command) {
+            (Some(_), Some(_)) => {
+                return Err("Set only one of 'token_env' and 'token_command'".to_string());
+            }
+            (Some(var), None) => Some(TokenSource::Env(var)),
+            (None, Some(command)) => Some(TokenSource::Command(command)),
+            (None, None) => None,
+        };
+
+        let confirm = match raw.confirm {
+            None | Some(toml::Value::Boolean(true)) => Confirmation::Once,
+            Some(toml::Value::Boolean(false)) => Confirmation::Skip,
+            Some(toml::Value::String(s)) if s == "twice" => Confirmation::Twice,
+            Some(other) => {
+                return Err(format!(
+                    "Invalid confirm {other}, expected true, false or \"twice\""
+                ));
+            }
+        };
+
+        // `{ command = "..." }` values come from a command, and are secret
+        let mut secret_inputs = raw.secret_inputs;
+        let mut input_commands = IndexMap::new();
+        let inputs = raw.inputs.map(|inputs| {
+            inputs
+                .into_iter()
+                .filter_map(|(name, value)| match input_command(&value) {
+                    Some(command) => {
+                        if !secret_inputs.contains(&name) {
+                            secret_inputs.push(name.clone());
+                        }
+                        input_commands.insert(name, command.to_string());
+                        None
+                    }
+                    None => Some((name, input_value_to_string(value))),
+                })
+                .collect()
+        });
 
         Ok(WorkflowRef {
             owner,
             repo,
-            workflow: raw.workflow,
+            workflow,
             git_ref: raw.git_ref,
-            inputs: raw.inputs,
+            inputs,
+            inputs_from,
+            token,
+            schema_file: raw.schema_file,
+            label: raw.label,
+            secret_inputs,
+            input_commands,
+            input_order: raw.input_order,
+            pre_dispatch: raw.pre_dispatch,
+            post_dispatch: raw.post_dispatch,
+            confirm,
         })
     }
 }
@@ -81,35 +498,529 @@ impl TryFrom<WorkflowRefRaw> for WorkflowRef {
 // Helpers
 // -----------------------------------------------------------------------------
 
-/// Load configuration from disk.
+/// Reduce a configured workflow to its filename.
+///
+/// Accepts `deploy.yml` or the file's path, `.github/workflows/deploy.yml`
+/// (optionally `./`-prefixed).  The contents API needs the path and the
+/// dispatch API the filename; both are derived from the filename.  GitHub
+/// only runs workflows directly under `.github/workflows`, so other paths are
+/// rejected.
+fn workflow_file_name(workflow: &str) -> Result<String, String> {
+    let name = workflow.trim_start_matches("./");
+    let name = name.strip_prefix(".github/workflows/").unwrap_or(name);
+    if name.is_empty() || name.contains('/') {
+        return Err(format!(
+            "Invalid workflow '{workflow}', expected a file name like 'deploy.yml' \
+             or a path directly under .github/workflows"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// The command of a `{ command = "..." }` input value, whose value is the
+/// command's output.  Any other table is a JSON input.
+fn input_command(value: &toml::Value) -> Option<&str> {
+    let table = value.as_table()?;
+    match table.get("command") {
+        Some(toml::Value::String(command)) if table.len() == 1 => Some(command),
+        _ => None,
+    }
+}
+
+/// Convert a configured input value to the string GitHub expects.  Tables and
+/// arrays are sent as compact JSON for workflows that take JSON payloads.
+fn input_value_to_string(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s,
+        toml::Value::Integer(n) => n.to_string(),
+        toml::Value::Float(n) => n.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(dt) => dt.to_string(),
+        value @ (toml::Value::Array(_) | toml::Value::Table(_)) => {
+            serde_json::to_string(&value).expect("TOML values serialize to JSON")
+        }
+    }
+}
+
+/// Load configuration from disk, from the file chosen by [`config_path`].
+pub fn load_config(path: Option<&Path>) -> Result<Config> {
+    let config_path = config_path(path)?;
+    let content =
+        read_to_string(&config_path).with_context(|| format!("Failed to read {config_path:?}"))?;
+
+    parse_config(&content, Some(&config_path))
+}
+
+/// Resolve which config file to read.
 ///
-/// Searches for config in order:
+/// Uses `path` if given, otherwise searches in order:
 /// 1. `./config.toml` (current directory)
 /// 2. `~/.config/gh-dispatch/config.toml` (user config)
-pub fn load_config() -> Result<Config> {
+pub fn config_path(path: Option<&Path>) -> Result<PathBuf> {
     let local = PathBuf::from("./config.toml");
-    let home_config = {
-        let home = std::env::var_os("HOME").context("HOME not set")?;
-        PathBuf::from(home)
-            .join(".config")
-            .join("gh-dispatch")
-            .join("config.toml")
-    };
+    let home_config = home_dir()?
+        .join(".config")
+        .join("gh-dispatch")
+        .join("config.toml");
 
-    let config_path = if local.exists() {
-        local
+    if let Some(path) = path {
+        Ok(path.to_path_buf())
+    } else if local.exists() {
+        Ok(local)
     } else if home_config.exists() {
-        home_config
+        Ok(home_config)
     } else {
         bail!(
             "No config file found. Checked:\n  {}\n  {}",
             local.display(),
             home_config.display()
         )
+    }
+}
+
+/// Load configuration from an `https://` URL.
+///
+/// A cached copy younger than an hour is used without fetching; an older
+/// one is used (with a warning) only if the fetch fails.
+pub async fn load_remote_config(url: &str) -> Result<Config> {
+    let cache_path = remote_cache_path(url)?;
+    let cache_age = std::fs::metadata(&cache_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+    if cache_age.is_some_and(|age| age < Duration::from_secs(REMOTE_CONFIG_TTL))
+        && let Ok(content) = read_to_string(&cache_path)
+    {
+        return parse_config(&content, None);
+    }
+
+    let content = match fetch_text(url).await {
+        Ok(content) => content,
+        Err(err) => {
+            let Ok(content) = read_to_string(&cache_path) else {
+                return Err(err.context(format!("Failed to fetch config from {url}")));
+            };
+            let mut config = parse_config(&content, None)?;
+            config.warnings.push(format!(
+                "Failed to fetch config from {url}, using cached copy: {}",
+                err.root_cause()
+            ));
+            return Ok(config);
+        }
     };
 
-    let content =
-        read_to_string(&config_path).with_context(|| format!("Failed to read {config_path:?}"))?;
+    // Validate before caching so an error page never replaces a good copy
+    let config = parse_config(&content, None)
+        .with_context(|| format!("{url} did not return a valid TOML config"))?;
+    if let Some(dir) = cache_path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let _ = std::fs::write(&cache_path, &content);
+    Ok(config)
+}
 
-    toml::from_str(&content).context("Failed to parse config TOML")
+/// Parse config TOML and expand `{{env.name}}` references in inputs.
+///
+/// `path` is the local config file, which `include` paths are relative to;
+/// remote configs, which have none, can't use `include`.
+fn parse_config(content: &str, path: Option<&Path>) -> Result<Config> {
+    let mut config: Config = toml::from_str(content).context("Failed to parse config TOML")?;
+    expand_inputs(&mut config.apps)?;
+    if !config.include.is_empty() {
+        let path = path.context("include is only supported in local config files")?;
+        apply_includes(&mut config, path)?;
+    }
+    apply_default_owner(&mut config)?;
+    if let Some(proxy) = &config.http.proxy {
+        Proxy::parse(proxy).context("Invalid [http] proxy")?;
+    }
+    for (name, value) in &config.http.headers {
+        HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid [http] header name '{name}'"))?;
+        HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for [http] header '{name}'"))?;
+    }
+    Ok(config)
+}
+
+/// Give workflows whose `repo` has no owner the default one, from
+/// `GH_DISPATCH_OWNER` or `default_owner`.
+fn apply_default_owner(config: &mut Config) -> Result<()> {
+    let default_owner = std::env::var("GH_DISPATCH_OWNER")
+        .ok()
+        .filter(|owner| !owner.is_empty())
+        .or(config.default_owner.clone());
+    for (app_name, app) in &mut config.apps {
+        for (workflow_name, workflow_ref) in &mut app.workflows {
+            if !workflow_ref.owner.is_empty() {
+                continue;
+            }
+            let Some(owner) = &default_owner else {
+                bail!(
+                    "Workflow '{workflow_name}' in app '{app_name}' has repo '{}' without an \
+                     owner; use 'owner/repo' or set default_owner or GH_DISPATCH_OWNER",
+                    workflow_ref.repo
+                );
+            };
+            workflow_ref.owner = owner.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Expand `{{env.name}}` references in every app's inputs.
+fn expand_inputs(apps: &mut IndexMap<String, AppConfig>) -> Result<()> {
+    for (app_name, app) in apps {
+        for (workflow_name, workflow_ref) in &mut app.workflows {
+            for value in workflow_ref.inputs.iter_mut().flat_map(|i| i.values_mut()) {
+                *value = expand_env(value, &app.env).with_context(|| {
+                    format!("Invalid input for workflow '{workflow_name}' in app '{app_name}'")
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge the apps of each `include`d file into `config`.
+///
+/// Files are merged in order, each pattern's matches sorted by name, and the
+/// including file's own apps last; a later definition of an app replaces an
+/// earlier one unless `include_conflict = "error"`.
+fn apply_includes(config: &mut Config, config_path: &Path) -> Result<()> {
+    let dir = config_path.parent().unwrap_or(Path::new(""));
+    let mut apps: IndexMap<String, AppConfig> = IndexMap::new();
+    let mut merge = |from: IndexMap<String, AppConfig>, source: &Path| -> Result<()> {
+        for (name, app) in from {
+            if apps.contains_key(&name) && config.include_conflict == IncludeConflict::Error {
+                bail!(
+                    "App '{name}' in {} is already defined in another config file",
+                    source.display()
+                );
+            }
+            apps.insert(name, app);
+        }
+        Ok(())
+    };
+
+    for pattern in &config.include {
+        let pattern_path = dir.join(pattern);
+        let paths = if pattern.contains(['*', '?']) {
+            let paths = expand_glob(&pattern_path);
+            if paths.is_empty() {
+                bail!("include '{pattern}' matched no files");
+            }
+            paths
+        } else {
+            vec![pattern_path]
+        };
+        for path in paths {
+            let content = read_to_string(&path)
+                .with_context(|| format!("Failed to read included config {}", path.display()))?;
+            let mut included: IncludedConfig = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse included config {}", path.display()))?;
+            expand_inputs(&mut included.apps)?;
+            merge(included.apps, &path)?;
+        }
+    }
+    merge(std::mem::take(&mut config.apps), config_path)?;
+
+    config.apps = apps;
+    Ok(())
+}
+
+/// Expand `*` and `?` wildcards in a path, one component at a time.
+///
+/// Wildcards don't match a leading `.`, as in a shell.  Returns the existing
+/// matching paths, sorted within each directory.
+fn expand_glob(pattern: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if !part.contains(['*', '?']) {
+            for path in &mut paths {
+                path.push(component);
+            }
+            continue;
+        }
+        let mut matches = Vec::new();
+        for dir in &paths {
+            let read_from = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            let Ok(entries) = std::fs::read_dir(read_from) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| !name.starts_with('.') && wildcard_match(&part, name))
+                .collect();
+            names.sort();
+            matches.extend(names.into_iter().map(|name| dir.join(name)));
+        }
+        paths = matches;
+    }
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// Match `name` against a pattern where `*` is any run of characters and `?`
+/// any single character.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Backtracking to the most recent `*` is enough for these two wildcards
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The user's home directory.
+fn home_dir() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("HOME not set")?;
+    Ok(PathBuf::from(home))
+}
+
+/// Where a remote config's cached copy lives, named after its URL.
+pub fn remote_cache_path(url: &str) -> Result<PathBuf> {
+    let name: String = url
+        .trim_start_matches("https://")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(home_dir()?
+        .join(".cache")
+        .join("gh-dispatch")
+        .join(format!("{name}.toml")))
+}
+
+/// Replace `{{env.name}}` references with values from the app's `env` map.
+///
+/// Other `{{...}}` text (such as GitHub `${{ }}` expressions) is left as-is.
+fn expand_env(value: &str, env: &IndexMap<String, String>) -> Result<String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let token = &rest[start + 2..start + len];
+        expanded.push_str(&rest[..start]);
+        match token.trim().strip_prefix("env.") {
+            Some(key) => match env.get(key) {
+                Some(v) => expanded.push_str(v),
+                None => bail!("'{value}' references undefined env value '{key}'"),
+            },
+            None => expanded.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Resolve the ordered chain of workflows needed to run `workflow`.
+///
+/// Workflows referenced through `inputs_from` must run (and complete) first so
+/// their outputs are available.  Returns the upstream workflows in dependency
+/// order, followed by `workflow` itself.
+pub fn resolve_chain(app: &AppConfig, workflow: &str) -> Result<Vec<String>> {
+    fn visit(
+        app: &AppConfig,
+        name: &str,
+        visiting: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        if order.iter().any(|n| n == name) {
+            return Ok(());
+        }
+        if !visiting.insert(name.to_string()) {
+            bail!("Workflow '{name}' has a circular inputs_from reference");
+        }
+        let workflow_ref = app
+            .workflows
+            .get(name)
+            .with_context(|| format!("inputs_from references unknown workflow '{name}'"))?;
+        for source in workflow_ref.inputs_from.values() {
+            visit(app, &source.workflow, visiting, order)?;
+        }
+        visiting.remove(name);
+        order.push(name.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(app, workflow, &mut HashSet::new(), &mut order)?;
+    Ok(order)
+}
+
+/// Resolve the ordered list of workflows run by a pipeline.
+///
+/// Each stage is expanded with [`resolve_chain`]; workflows shared between
+/// stages run only once.
+pub fn resolve_pipeline(app: &AppConfig, pipeline: &str) -> Result<Vec<String>> {
+    let stages = &app
+        .pipelines
+        .get(pipeline)
+        .with_context(|| format!("Pipeline '{pipeline}' not found"))?
+        .stages;
+
+    let mut order: Vec<String> = Vec::new();
+    for stage in stages {
+        if !app.workflows.contains_key(stage) {
+            bail!("Pipeline '{pipeline}' references unknown workflow '{stage}'");
+        }
+        for name in resolve_chain(app, stage)? {
+            if !order.contains(&name) {
+                order.push(name);
+            }
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(content: &str) -> String {
+        format!("{:#}", parse_config(content, None).unwrap_err())
+    }
+
+    #[test]
+    fn app_tables_split_reserved_keys_from_workflows() {
+        let config = parse_config(
+            r#"
+[apps.web]
+deploy = { repo = "acme/web", workflow = "deploy.yml" }
+env = { release = "2.4" }
+pipelines = { ship = { stages = ["deploy"] } }
+"#,
+            None,
+        )
+        .unwrap();
+        let app = &config.apps["web"];
+        assert_eq!(app.workflows.keys().collect::<Vec<_>>(), ["deploy"]);
+        assert_eq!(app.env["release"], "2.4");
+        assert_eq!(app.pipelines["ship"].stages, ["deploy"]);
+    }
+
+    #[test]
+    fn workflow_named_like_a_reserved_key_is_rejected() {
+        for key in ["env", "pipelines"] {
+            let error = parse_error(&format!(
+                "[apps.web]\n{key} = {{ repo = \"acme/web\", workflow = \"env.yml\" }}\n"
+            ));
+            assert!(error.contains(&format!("'{key}' is reserved")), "{error}");
+        }
+    }
+
+    #[test]
+    fn workflow_errors_name_the_workflow_and_reason() {
+        let error = parse_error(
+            "[apps.web]\ndeploy = { repo = \"acme/web\", workflow = \"ci/deploy.yml\" }\n",
+        );
+        assert!(error.contains("Workflow 'deploy': "), "{error}");
+        assert!(
+            error.contains("directly under .github/workflows"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn workflow_file_name_accepts_names_and_paths() {
+        for workflow in [
+            "deploy.yml",
+            ".github/workflows/deploy.yml",
+            "./.github/workflows/deploy.yml",
+        ] {
+            assert_eq!(workflow_file_name(workflow).as_deref(), Ok("deploy.yml"));
+        }
+    }
+
+    #[test]
+    fn workflow_file_name_rejects_other_paths() {
+        for workflow in [
+            ".github/workflows/nested/deploy.yml",
+            "ci/deploy.yml",
+            ".github/workflows/",
+            "",
+        ] {
+            assert!(workflow_file_name(workflow).is_err(), "{workflow}");
+        }
+    }
+
+    #[test]
+    fn wildcard_match_handles_star_and_question_mark() {
+        assert!(wildcard_match("*.toml", "apps.toml"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("app?.toml", "app1.toml"));
+        assert!(wildcard_match("**", "anything"));
+        assert!(!wildcard_match("app?.toml", "app.toml"));
+        assert!(!wildcard_match("*.toml", "apps.yml"));
+        assert!(!wildcard_match("apps", "apps.toml"));
+    }
+
+    #[test]
+    fn wildcard_match_backtracks_to_the_last_star() {
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(wildcard_match("*ab", "aab"));
+        assert!(wildcard_match("a*b?d", "abxbcd"));
+        assert!(!wildcard_match("a*b*c", "axxbyy"));
+        assert!(!wildcard_match("*ab", "aba"));
+    }
+
+    #[test]
+    fn expand_glob_matches_per_component_and_skips_dotfiles() {
+        let dir = std::env::temp_dir().join(format!("gh-dispatch-glob-{}", std::process::id()));
+        for name in ["team-b", "team-a", ".hidden"] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("apps.toml"), "").unwrap();
+        }
+        std::fs::write(dir.join("team-a").join("notes.md"), "").unwrap();
+        std::fs::write(dir.join(".apps.toml"), "").unwrap();
+
+        let found = expand_glob(&dir.join("*").join("*.toml"));
+        let dotfiles = expand_glob(&dir.join("*apps.toml"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            found,
+            [
+                dir.join("team-a").join("apps.toml"),
+                dir.join("team-b").join("apps.toml"),
+            ]
+        );
+        assert!(dotfiles.is_empty(), "{dotfiles:?}");
+    }
 }
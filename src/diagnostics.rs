@@ -0,0 +1,75 @@
+//! The `info` subcommand.
+//!
+//! Reports the version, config file, token source, proxy, and authenticated user
+//! that a dispatch would use.  The token itself is never printed.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use crate::config::{config_path, load_config, load_remote_config, remote_cache_path};
+use crate::github::{configure_http, create_client, get_current_login, proxy_for, token_source};
+
+const API_HOST: &str = "https://api.github.com";
+
+/// Print diagnostics for bug reports.  Problems are reported inline rather
+/// than as errors so every line is shown.
+pub async fn print_diagnostics(config: Option<&str>) -> Result<()> {
+    let path = match config {
+        Some(url) if url.starts_with("https://") => match remote_cache_path(url) {
+            Ok(cache) => Ok(format!("{url} (cached at {})", cache.display())),
+            Err(err) => Err(format!("{url} ({err})")),
+        },
+        path => config_path(path.map(Path::new))
+            .map(|path| path.display().to_string())
+            .map_err(|err| format!("{err:#}").red().to_string()),
+    };
+    let loaded = match config {
+        Some(url) if url.starts_with("https://") => load_remote_config(url).await,
+        path => load_config(path.map(Path::new)),
+    };
+    // The config's [http] table decides the proxy (and the login check's
+    // route) as it would for a dispatch; without a config only the
+    // environment does
+    let (config, proxy_source) = match (path, loaded) {
+        (Ok(path), Ok(loaded)) => {
+            let source = if loaded.http.proxy.is_some() {
+                "config"
+            } else {
+                "environment"
+            };
+            (path, configure_http(&loaded.http).map(|()| source))
+        }
+        (Ok(path), Err(err)) => (
+            format!("{path} ({})", format!("{err:#}").red()),
+            Ok("environment"),
+        ),
+        // Loading failed the same way
+        (Err(path), _) => (path, Ok("environment")),
+    };
+    let proxy = match proxy_source.and_then(|source| Ok((proxy_for(API_HOST)?, source))) {
+        Ok((Some(url), source)) => format!("{url} (from {source})"),
+        Ok((None, _)) => "none".to_string(),
+        Err(err) => format!("{err:#}").red().to_string(),
+    };
+    let login = match create_client() {
+        Ok(client) => match get_current_login(&client).await {
+            Ok(login) => login,
+            Err(err) => err.root_cause().to_string().red().to_string(),
+        },
+        Err(err) => format!("{err:#}").red().to_string(),
+    };
+
+    let rows = [
+        ("Version", env!("CARGO_PKG_VERSION").to_string()),
+        ("Config", config),
+        ("Token", token_source().to_string()),
+        ("API host", API_HOST.to_string()),
+        ("Proxy", proxy),
+        ("Login", login),
+    ];
+    for (key, value) in rows {
+        println!("  {}  {}", format!("{key:<8}").dimmed(), value);
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! The `discover` subcommand.
+//!
+//! Lists a repository's workflows with their display names and whether they
+//! have a `workflow_dispatch` trigger, to help write config entries.
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use futures::future::join_all;
+use octocrab::Octocrab;
+use serde::Serialize;
+
+use crate::cli::OutputFormat;
+use crate::github::{get_workflow_schema, list_workflows};
+use crate::ui::create_spinner;
+
+/// A workflow found in the repository.
+#[derive(Debug, Serialize)]
+struct DiscoveredWorkflow {
+    file: String,
+    name: String,
+    state: String,
+    /// `None` if the workflow file couldn't be read
+    dispatchable: Option<bool>,
+}
+
+/// List the workflows in `repo` (`owner/repo`), or print a config snippet
+/// for one of them.
+pub async fn discover(
+    client: &Octocrab,
+    repo: &str,
+    snippet: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let (owner, name) = repo
+        .split_once('/')
+        .with_context(|| format!("Invalid repo format '{repo}', expected 'owner/repo'"))?;
+
+    let spinner = create_spinner("Listing workflows...");
+    let workflows = list_workflows(client, owner, name).await?;
+
+    // Only files under .github/workflows can be read; others (e.g. dynamic
+    // Dependabot workflows) are never dispatchable
+    let discovered = join_all(workflows.into_iter().map(|wf| async move {
+        let file = wf
+            .path
+            .strip_prefix(".github/workflows/")
+            .map(str::to_string);
+        let dispatchable = match &file {
+            Some(file) => get_workflow_schema(client, owner, name, file, false)
+                .await
+                .ok()
+                .map(|schema| schema.dispatchable),
+            None => Some(false),
+        };
+        DiscoveredWorkflow {
+            file: file.unwrap_or(wf.path),
+            name: wf.name,
+            state: wf.state,
+            dispatchable,
+        }
+    }))
+    .await;
+    spinner.finish_and_clear();
+
+    if let Some(file) = snippet {
+        let Some(wf) = discovered.iter().find(|wf| wf.file == file) else {
+            bail!("Workflow '{file}' not found in {repo}");
+        };
+        if wf.dispatchable == Some(false) {
+            bail!("Workflow '{file}' has no workflow_dispatch trigger");
+        }
+        let key = file.trim_end_matches(".yml").trim_end_matches(".yaml");
+        println!("{key} = {{ repo = \"{repo}\", workflow = \"{file}\" }}");
+        return Ok(());
+    }
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&discovered)?);
+        return Ok(());
+    }
+
+    let file_width = discovered
+        .iter()
+        .map(|wf| wf.file.chars().count())
+        .max()
+        .unwrap_or(0);
+    let name_width = discovered
+        .iter()
+        .map(|wf| wf.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for wf in &discovered {
+        let dispatchable = match wf.dispatchable {
+            Some(true) => "dispatchable".green(),
+            Some(false) => "not dispatchable".dimmed(),
+            None => "unknown".yellow(),
+        };
+        let state = if wf.state == "active" {
+            String::new()
+        } else {
+            format!(" ({})", wf.state).dimmed().to_string()
+        };
+        println!(
+            "  {:<file_width$}  {:<name_width$}  {dispatchable}{state}",
+            wf.file.bold(),
+            wf.name,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//! Repository detection from the local git checkout.
+//!
+//! Reads the `origin` remote, as `gh` does, and parses its URL into a host
+//! and `owner/repo`.  SSH (`git@host:owner/repo.git`, `ssh://...`) and
+//! HTTPS forms are understood, for github.com and GitHub Enterprise hosts.
+
+use anyhow::{Context, Result, bail};
+use std::process::Command;
+
+const GITHUB_HOST: &str = "github.com";
+
+/// A GitHub repository identified from a git remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitRemote {
+    /// Web host, e.g. `github.com` or a GitHub Enterprise hostname
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl GitRemote {
+    /// Whether the repository lives on github.com rather than an Enterprise host.
+    pub fn is_github_com(&self) -> bool {
+        self.host == GITHUB_HOST
+    }
+}
+
+/// Identify the repository of the current directory's `origin` remote.
+pub fn repo_from_git() -> Result<GitRemote> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .context("Failed to run `git`; is it installed?")?;
+    if !output.status.success() {
+        bail!(
+            "Could not read the 'origin' remote: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    parse_remote_url(&url)
+}
+
+/// Parse a git remote URL into its GitHub host and repository.
+///
+/// Hosts other than github.com are accepted when they look like GitHub
+/// Enterprise (`*.ghe.com`, a name containing "github") or match `GH_HOST`.
+pub fn parse_remote_url(url: &str) -> Result<GitRemote> {
+    let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+        // ssh://git@host:22/owner/repo.git, https://user@host/owner/repo
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        (host.split(':').next().unwrap_or(host), path)
+    } else if let Some((user_host, path)) = url.split_once(':') {
+        // scp-like: git@host:owner/repo.git
+        (user_host.rsplit('@').next().unwrap_or(user_host), path)
+    } else {
+        bail!("Unrecognised git remote URL '{url}'");
+    };
+
+    let host = host.to_ascii_lowercase();
+    let is_github = host == GITHUB_HOST
+        || host.ends_with(".ghe.com")
+        || host.contains("github")
+        || std::env::var("GH_HOST").is_ok_and(|h| h.eq_ignore_ascii_case(&host));
+    if !is_github {
+        bail!(
+            "Remote 'origin' ({url}) is not a GitHub repository; \
+             set GH_HOST={host} if it is a GitHub Enterprise host"
+        );
+    }
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let Some((owner, repo)) = path
+        .split_once('/')
+        .filter(|(o, r)| !o.is_empty() && !r.is_empty() && !r.contains('/'))
+    else {
+        bail!("Could not find owner/repo in git remote URL '{url}'");
+    };
+
+    Ok(GitRemote {
+        host,
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(url: &str) -> (String, String, String) {
+        let remote = parse_remote_url(url).unwrap();
+        (remote.host, remote.owner, remote.repo)
+    }
+
+    fn expected(host: &str) -> (String, String, String) {
+        (host.to_string(), "acme".to_string(), "web".to_string())
+    }
+
+    #[test]
+    fn parses_github_com_remotes() {
+        for url in [
+            "ssh://git@github.com:22/acme/web.git",
+            "ssh://git@github.com/acme/web",
+            "git@github.com:acme/web.git",
+            "https://github.com/acme/web.git",
+            "https://user@GitHub.com/acme/web/",
+        ] {
+            assert_eq!(remote(url), expected("github.com"), "{url}");
+        }
+    }
+
+    #[test]
+    fn parses_enterprise_remotes() {
+        assert_eq!(
+            remote("git@github.acme.corp:acme/web.git"),
+            expected("github.acme.corp")
+        );
+        assert_eq!(
+            remote("https://acme.ghe.com/acme/web"),
+            expected("acme.ghe.com")
+        );
+    }
+
+    #[test]
+    fn rejects_other_remotes() {
+        for url in [
+            "git@gitlab.com:acme/web.git",
+            "https://github.com/acme",
+            "https://github.com/acme/web/extra",
+            "/srv/git/web.git",
+        ] {
+            assert!(parse_remote_url(url).is_err(), "{url}");
+        }
+    }
+}
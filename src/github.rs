@@ -5,20 +5,51 @@
 //! - Fetching workflow schemas
 //! - Dispatching workflows
 //! - Polling workflow run status
+//! - Reading outputs published by completed runs
+//! - Approving deployments waiting on review
 
 use anyhow::{Context, Result, bail};
 use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Utc};
+use http::Uri;
+use http::header::{
+    AUTHORIZATION, CACHE_CONTROL, ETAG, HeaderMap, HeaderName, HeaderValue, IF_NONE_MATCH,
+    LOCATION, USER_AGENT,
+};
+use hyper_rustls::HttpsConnectorBuilder;
+use hyper_util::rt::TokioExecutor;
 use indexmap::IndexMap;
-use octocrab::Octocrab;
-use octocrab::models::workflows::Run;
+use octocrab::models::workflows::{Run, WorkFlow};
 use octocrab::models::{CheckRunId, RunId};
 use octocrab::params::checks::CheckRunAnnotation;
-use serde::Deserialize;
+use octocrab::service::middleware::auth_header::AuthHeaderLayer;
+use octocrab::service::middleware::base_uri::BaseUriLayer;
+use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
+use octocrab::{
+    AuthState, DefaultOctocrabBuilderConfig, NoAuth, NoSvc, NotLayerReady, Octocrab,
+    OctocrabBuilder,
+};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::config::{HttpConfig, TokenSource};
+use crate::proxy::{ProxyConnector, ProxySettings};
 
 const POLL_DELAY: u64 = 2;
+const GH_AUTH_TIMEOUT: u64 = 5; // seconds
+const TOKEN_COMMAND_TIMEOUT: u64 = 30; // seconds
+const TRIGGERED_RUN_ATTEMPTS: u32 = 5;
+const JOBS_PER_PAGE: usize = 100; // the API maximum
+const MAX_REDIRECTS: usize = 5;
+const SECONDARY_RATE_LIMIT_WAIT: u64 = 60; // seconds, when GitHub sends no Retry-After
 
 // -----------------------------------------------------------------------------
 // Types
@@ -30,12 +61,21 @@ pub struct WorkflowSchema {
     pub name: String,
     /// Input definitions from `workflow_dispatch` trigger
     pub inputs: IndexMap<String, WorkflowInput>,
+    /// Inputs that could not be parsed and were skipped, with the reason
+    pub skipped: Vec<String>,
+    /// Whether the workflow has a `workflow_dispatch` trigger
+    pub dispatchable: bool,
 }
 
 /// A single workflow input definition from `workflow_dispatch.inputs`.
-#[derive(Debug, Deserialize, Clone)]
+///
+/// Unknown keys (e.g. `deprecationMessage`) are ignored, and scalar defaults
+/// and options (`default: true`, `options: [1, 2]`) are read as strings.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct WorkflowInput {
     /// Default value if not provided
+    #[serde(deserialize_with = "scalar_string")]
     pub default: Option<String>,
     /// Description shown in GitHub UI
     pub description: Option<String>,
@@ -43,11 +83,120 @@ pub struct WorkflowInput {
     #[serde(rename = "type")]
     pub input_type: Option<String>,
     /// Available options (only for choice type)
+    #[serde(deserialize_with = "scalar_strings")]
     pub options: Option<Vec<String>>,
     /// Whether the input is required
     pub required: Option<bool>,
 }
 
+/// Deserialize an optional YAML scalar (string, bool or number) as a string.
+fn scalar_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<Value>::deserialize(deserializer)?
+        .map(|v| scalar_to_string(&v).ok_or_else(|| serde::de::Error::custom("expected a scalar")))
+        .transpose()
+}
+
+/// Deserialize an optional list of YAML scalars as strings.
+fn scalar_strings<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<Vec<Value>>::deserialize(deserializer)?
+        .map(|values| {
+            values
+                .iter()
+                .map(|v| {
+                    scalar_to_string(v).ok_or_else(|| serde::de::Error::custom("expected a scalar"))
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// The string form of a YAML scalar, or `None` for null and collections.
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Response from `GET /repos/{owner}/{repo}/environments`.
+#[derive(Debug, Deserialize)]
+struct EnvironmentsResponse {
+    environments: Vec<Environment>,
+}
+
+/// A deployment environment configured on a repository.
+#[derive(Debug, Deserialize)]
+struct Environment {
+    name: String,
+}
+
+/// A deployment of a run waiting on a required reviewer, from
+/// `GET /repos/{owner}/{repo}/actions/runs/{run_id}/pending_deployments`.
+#[derive(Debug, Deserialize)]
+pub struct PendingDeployment {
+    pub environment: PendingEnvironment,
+    /// Whether the authenticated user may approve this deployment
+    pub current_user_can_approve: bool,
+}
+
+/// The environment a pending deployment targets.
+#[derive(Debug, Deserialize)]
+pub struct PendingEnvironment {
+    pub id: u64,
+    pub name: String,
+}
+
+/// GitHub's secondary rate limit was hit and the request should be retried
+/// after `retry_after`.
+///
+/// Distinct from the primary (hourly) limit: GitHub answers 403 or 429 with a
+/// `Retry-After` header when requests arrive too fast or too concurrently.
+#[derive(Debug)]
+pub struct SecondaryRateLimit {
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for SecondaryRateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GitHub secondary rate limit hit; retry after {}s",
+            self.retry_after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for SecondaryRateLimit {}
+
+/// Responses remembered by their ETag, so repeating a GET can be a
+/// conditional request.
+///
+/// GitHub answers an unchanged resource with `304 Not Modified`, which
+/// doesn't count against the rate limit, and the remembered body is reused.
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    /// Route -> (ETag, body)
+    entries: HashMap<String, (HeaderValue, String)>,
+    /// Whether a response has changed since the last `take_changed`
+    changed: bool,
+}
+
+impl ResponseCache {
+    /// Whether any response differed from the remembered one since this was
+    /// last called.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
+
 // -----------------------------------------------------------------------------
 // Job / Step Types
 // -----------------------------------------------------------------------------
@@ -55,13 +204,16 @@ pub struct WorkflowInput {
 /// Response from `GET /repos/{owner}/{repo}/actions/runs/{run_id}/jobs`.
 #[derive(Debug, Deserialize)]
 pub struct JobsResponse {
+    /// Number of jobs across all pages, when GitHub sends it
+    #[serde(default)]
+    pub total_count: Option<usize>,
     pub jobs: Vec<Job>,
 }
 
 /// Status of a job or step.  `#[serde(other)]` keeps us safe against new
 /// statuses GitHub may add in the future (e.g. "waiting" is not in
 /// octocrab's enum but is returned for concurrency-gated jobs).
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum JobStatus {
     Queued,
@@ -74,7 +226,7 @@ pub enum JobStatus {
 }
 
 /// Conclusion of a completed job or step.
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum JobConclusion {
     Success,
@@ -88,6 +240,22 @@ pub enum JobConclusion {
     Unknown,
 }
 
+impl JobConclusion {
+    /// The conclusion as GitHub spells it (e.g. "timed_out").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobConclusion::Success => "success",
+            JobConclusion::Failure => "failure",
+            JobConclusion::Cancelled => "cancelled",
+            JobConclusion::Skipped => "skipped",
+            JobConclusion::Neutral => "neutral",
+            JobConclusion::ActionRequired => "action_required",
+            JobConclusion::TimedOut => "timed_out",
+            JobConclusion::Unknown => "unknown",
+        }
+    }
+}
+
 /// A single job within a workflow run.
 #[derive(Debug, Deserialize, Clone)]
 pub struct Job {
@@ -104,6 +272,16 @@ pub struct Job {
     pub steps: Vec<Step>,
 }
 
+impl Job {
+    /// Seconds the job ran for, if it has both start and completion timestamps.
+    pub fn duration_seconds(&self) -> Option<i64> {
+        match (&self.started_at, &self.completed_at) {
+            (Some(start), Some(end)) => Some((*end - *start).num_seconds().max(0)),
+            _ => None,
+        }
+    }
+}
+
 /// A single step within a job.
 #[derive(Debug, Deserialize, Clone)]
 pub struct Step {
@@ -128,11 +306,233 @@ pub fn check_run_id_from_url(url: &str) -> Option<u64> {
 /// 1. `GITHUB_TOKEN` environment variable
 /// 2. `gh auth token` CLI command (if gh is installed and authenticated)
 pub fn create_client() -> Result<Octocrab> {
-    let token = get_token()?;
-    Octocrab::builder()
-        .personal_token(token)
-        .build()
-        .context("Failed to create GitHub client")
+    client_with_token(get_token()?)
+}
+
+/// Create an octocrab client authenticated from a target-specific token source.
+pub fn create_client_for(source: &TokenSource) -> Result<Octocrab> {
+    let token = match source {
+        TokenSource::Env(var) => std::env::var(var)
+            .with_context(|| format!("Token environment variable {var} is not set"))?,
+        TokenSource::Command(command) => {
+            let output = output_with_timeout(
+                Command::new("sh").args(["-c", command]),
+                Duration::from_secs(TOKEN_COMMAND_TIMEOUT),
+            )
+            .with_context(|| format!("Failed to run token command `{command}`"))?
+            .with_context(|| {
+                format!("Token command `{command}` did not finish within {TOKEN_COMMAND_TIMEOUT}s")
+            })?;
+            if !output.status.success() {
+                bail!(
+                    "Token command `{command}` failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
+    if token.is_empty() {
+        bail!("Token from {source} is empty");
+    }
+    client_with_token(token)
+}
+
+/// Create an octocrab client for a GitHub Enterprise host.
+///
+/// Mirrors `gh`: the token comes from `GH_ENTERPRISE_TOKEN` (or
+/// `GITHUB_ENTERPRISE_TOKEN`), else `gh auth token --hostname <host>`.
+pub fn create_client_for_host(host: &str) -> Result<Octocrab> {
+    let token = match ["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok())
+    {
+        Some(token) => token,
+        None => {
+            let output = output_with_timeout(
+                Command::new("gh").args(["auth", "token", "--hostname", host]),
+                Duration::from_secs(GH_AUTH_TIMEOUT),
+            )
+            .context(
+                "Failed to run `gh auth token`; set GH_ENTERPRISE_TOKEN or install the gh CLI",
+            )?
+            .with_context(|| {
+                format!(
+                    "`gh auth token --hostname {host}` did not respond within {GH_AUTH_TIMEOUT}s"
+                )
+            })?;
+            if !output.status.success() {
+                bail!(
+                    "No GH_ENTERPRISE_TOKEN found and `gh auth token --hostname {host}` failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
+    build_client(Some(host), Some(token))
+}
+
+/// The REST API's base URI for a web host, e.g. `https://api.github.com`.
+pub fn api_base_uri(host: &str) -> String {
+    // GHE.com tenants serve the API from a subdomain; Enterprise Server under /api/v3
+    if host == "github.com" || host.ends_with(".ghe.com") {
+        format!("https://api.{host}")
+    } else {
+        format!("https://{host}/api/v3")
+    }
+}
+
+/// The uploads API's base URI for a web host, e.g. `https://uploads.github.com`.
+fn upload_base_uri(host: &str) -> String {
+    if host == "github.com" || host.ends_with(".ghe.com") {
+        format!("https://uploads.{host}")
+    } else {
+        format!("https://{host}/api/uploads")
+    }
+}
+
+fn client_with_token(token: String) -> Result<Octocrab> {
+    build_client(None, Some(token))
+}
+
+/// Proxy and extra headers for every client, from [`configure_http`] or the
+/// environment.
+#[derive(Clone)]
+struct HttpSettings {
+    proxy: ProxySettings,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+static HTTP_SETTINGS: RwLock<Option<HttpSettings>> = RwLock::new(None);
+
+/// Apply the config's `[http]` table to clients created from now on.
+pub fn configure_http(http: &HttpConfig) -> Result<()> {
+    let settings = HttpSettings {
+        proxy: ProxySettings::resolve(http.proxy.as_deref())?,
+        headers: parse_headers(&http.headers)?,
+    };
+    *HTTP_SETTINGS.write().unwrap() = Some(settings);
+    Ok(())
+}
+
+/// Validate the `[http] headers` table.
+fn parse_headers(headers: &IndexMap<String, String>) -> Result<Vec<(HeaderName, HeaderValue)>> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid [http] header name '{name}'"))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value for [http] header '{name}'"))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+/// The HTTP settings in use, read from the environment if not configured.
+fn http_settings() -> Result<HttpSettings> {
+    if let Some(settings) = &*HTTP_SETTINGS.read().unwrap() {
+        return Ok(settings.clone());
+    }
+    let settings = HttpSettings {
+        proxy: ProxySettings::resolve(None)?,
+        headers: Vec::new(),
+    };
+    *HTTP_SETTINGS.write().unwrap() = Some(settings.clone());
+    Ok(settings)
+}
+
+/// URL of the proxy requests to `url` go through, if any.
+pub fn proxy_for(url: &str) -> Result<Option<String>> {
+    let uri: Uri = url
+        .parse()
+        .with_context(|| format!("Invalid URL '{url}'"))?;
+    let settings = http_settings()?;
+    Ok(settings
+        .proxy
+        .for_uri(&uri)
+        .map(|proxy| proxy.url().to_string()))
+}
+
+/// Build a client for the API of the web `host` (github.com if `None`),
+/// authenticated with `token` if given.
+fn build_client(host: Option<&str>, token: Option<String>) -> Result<Octocrab> {
+    let host = host.unwrap_or("github.com");
+    let settings = http_settings()?;
+    let builder = Octocrab::builder()
+        .base_uri(api_base_uri(host))
+        .and_then(|builder| builder.upload_uri(upload_base_uri(host)))
+        .context("Invalid GitHub Enterprise host")?;
+    if settings.proxy.is_set() {
+        return build_proxied_client(builder, settings, host, token);
+    }
+
+    let mut builder = builder;
+    if let Some(token) = token {
+        builder = builder.personal_token(token);
+    }
+    for (name, value) in settings.headers {
+        // Validated by `parse_headers`, so always visible ASCII
+        builder = builder.add_header(name, value.to_str().unwrap_or_default().to_string());
+    }
+    builder.build().context("Failed to create GitHub client")
+}
+
+/// Build a client whose connections go through the configured proxy.
+///
+/// octocrab's default client can't take a connector, so this assembles its
+/// service stack from the same middleware, with the timeouts and retries of
+/// `builder`.
+fn build_proxied_client(
+    builder: OctocrabBuilder<NoSvc, DefaultOctocrabBuilderConfig, NoAuth, NotLayerReady>,
+    settings: HttpSettings,
+    host: &str,
+    token: Option<String>,
+) -> Result<Octocrab> {
+    let connector = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .context("Failed to load TLS root certificates")?
+        .https_or_http()
+        .enable_http1()
+        .wrap_connector(ProxyConnector::new(settings.proxy));
+    let connector = builder.set_connect_timeout_service(connector);
+    let client = hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(connector);
+    let client = builder.set_connector_retry_service(client);
+
+    let base_uri: Uri = api_base_uri(host)
+        .parse()
+        .context("Invalid GitHub Enterprise host")?;
+    let upload_uri: Uri = upload_base_uri(host)
+        .parse()
+        .context("Invalid GitHub Enterprise host")?;
+    let mut headers = vec![(USER_AGENT, HeaderValue::from_static("octocrab"))];
+    headers.extend(settings.headers);
+    let auth_header = token
+        .map(|token| HeaderValue::from_str(&format!("Bearer {token}")))
+        .transpose()
+        .context("Invalid GitHub token")?;
+
+    let octocrab = OctocrabBuilder::new_empty()
+        .with_service(client)
+        .with_layer(&ExtraHeadersLayer::new(Arc::new(headers)))
+        .with_layer(&BaseUriLayer::new(base_uri.clone()))
+        .with_layer(&AuthHeaderLayer::new(auth_header, base_uri, upload_uri))
+        .with_auth(AuthState::None)
+        .build();
+    match octocrab {
+        Ok(octocrab) => Ok(octocrab),
+        Err(never) => match never {},
+    }
+}
+
+/// Describe where [`create_client`] reads its token from, without reading it.
+pub fn token_source() -> &'static str {
+    if std::env::var_os("GITHUB_TOKEN").is_some() {
+        "GITHUB_TOKEN environment variable"
+    } else {
+        "`gh auth token`"
+    }
 }
 
 /// Get GitHub token from environment or gh CLI.
@@ -142,19 +542,203 @@ fn get_token() -> Result<String> {
         return Ok(token);
     }
 
-    // Fall back to gh CLI
-    let output = std::process::Command::new("gh")
-        .args(["auth", "token"])
-        .output()
-        .context("Failed to run `gh auth token`")?;
+    // Fall back to gh CLI, bounded so a misconfigured gh can't hang the tool
+    let output = output_with_timeout(
+        Command::new("gh").args(["auth", "token"]),
+        Duration::from_secs(GH_AUTH_TIMEOUT),
+    )
+    .context("Failed to run `gh auth token`; set GITHUB_TOKEN or install the gh CLI")?
+    .with_context(|| {
+        format!(
+            "`gh auth token` did not respond within {GH_AUTH_TIMEOUT}s; \
+             set GITHUB_TOKEN directly instead"
+        )
+    })?;
 
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     } else {
-        bail!("No GITHUB_TOKEN found and `gh auth token` failed")
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "No GITHUB_TOKEN found and `gh auth token` failed: {}",
+            stderr.trim()
+        )
     }
 }
 
+/// GET an API route and deserialize its JSON response.
+///
+/// Unlike `Octocrab::get`, this sees the response headers, so a secondary
+/// rate limit is returned as a [`SecondaryRateLimit`] error carrying GitHub's
+/// `Retry-After` delay rather than as a generic 403.  With a `cache`, the
+/// request is conditional on the remembered response's ETag.
+async fn get_json<T: DeserializeOwned>(
+    client: &Octocrab,
+    route: &str,
+    cache: Option<&mut ResponseCache>,
+) -> Result<T> {
+    let cached = cache.as_ref().and_then(|cache| cache.entries.get(route));
+    let headers = cached.map(|(etag, _)| HeaderMap::from_iter([(IF_NONE_MATCH, etag.clone())]));
+    let response = client
+        ._get_with_headers(route, headers)
+        .await
+        .context("Request failed")?;
+    let status = response.status();
+    if status == http::StatusCode::NOT_MODIFIED
+        && let Some((_, body)) = cached
+    {
+        return serde_json::from_str(body).context("Unexpected response from GitHub");
+    }
+    if matches!(status.as_u16(), 403 | 429) {
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        let body = client.body_to_string(response).await.unwrap_or_default();
+        if retry_after.is_some() || body.contains("secondary rate limit") {
+            return Err(SecondaryRateLimit {
+                retry_after: Duration::from_secs(retry_after.unwrap_or(SECONDARY_RATE_LIMIT_WAIT)),
+            }
+            .into());
+        }
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["message"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        bail!("HTTP {status}: {message}");
+    }
+    let response = octocrab::map_github_error(response).await?;
+    let etag = response.headers().get(ETAG).cloned();
+    let body = client
+        .body_to_string(response)
+        .await
+        .context("Failed to read response")?;
+    let value = serde_json::from_str(&body).context("Unexpected response from GitHub")?;
+    if let Some(cache) = cache {
+        cache.changed = true;
+        match etag {
+            Some(etag) => cache.entries.insert(route.to_string(), (etag, body)),
+            None => cache.entries.remove(route),
+        };
+    }
+    Ok(value)
+}
+
+/// Run a command to completion, capturing its output.
+///
+/// Returns `None` (after killing the process) if it runs longer than `timeout`.
+fn output_with_timeout(command: &mut Command, timeout: Duration) -> Result<Option<Output>> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let deadline = Instant::now() + timeout;
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    Ok(Some(child.wait_with_output()?))
+}
+
+/// Fetch a text file over HTTPS, following redirects.
+///
+/// Each hop is checked on its own: requests to GitHub hosts carry the token,
+/// resolved only when first needed so other hosts never require one, and any
+/// other host (including one redirected to) is fetched anonymously.
+pub async fn fetch_text(url: &str) -> Result<String> {
+    fetch_following_redirects(
+        url,
+        "https",
+        |url| url.host().is_some_and(is_github_host),
+        get_token,
+    )
+    .await
+}
+
+/// [`fetch_text`] with the allowed scheme, the hosts trusted with the token
+/// and the token source spelled out.
+///
+/// The token is sent as a header of its own: octocrab only authenticates
+/// requests to its API host, which raw.githubusercontent.com isn't.
+async fn fetch_following_redirects(
+    url: &str,
+    scheme: &str,
+    trusted: impl Fn(&Uri) -> bool,
+    mut token: impl FnMut() -> Result<String>,
+) -> Result<String> {
+    let client = build_client(None, None)?;
+    let mut auth_headers = None;
+    let mut url: Uri = url
+        .parse()
+        .with_context(|| format!("Invalid URL '{url}'"))?;
+    for _ in 0..=MAX_REDIRECTS {
+        if url.scheme_str() != Some(scheme) {
+            bail!("Refusing to fetch {url}: only {scheme}:// URLs are supported");
+        }
+        let headers = if trusted(&url) {
+            if auth_headers.is_none() {
+                let value = HeaderValue::from_str(&format!("Bearer {}", token()?))
+                    .context("Invalid token")?;
+                auth_headers = Some(HeaderMap::from_iter([(AUTHORIZATION, value)]));
+            }
+            auth_headers.clone()
+        } else {
+            None
+        };
+
+        let response = client
+            ._get_with_headers(url.clone(), headers)
+            .await
+            .context("Request failed")?;
+        if response.status().is_redirection()
+            && let Some(location) = response.headers().get(LOCATION)
+        {
+            let location = location.to_str().context("Invalid redirect location")?;
+            url = redirect_target(&url, location)?;
+            continue;
+        }
+        if !response.status().is_success() {
+            bail!("HTTP {}", response.status());
+        }
+        return client
+            .body_to_string(response)
+            .await
+            .context("Failed to read response");
+    }
+    bail!("Too many redirects, last to {url}")
+}
+
+/// Hosts whose files may need the token, e.g. raw.githubusercontent.com for
+/// files in private repos.
+fn is_github_host(host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    host == "github.com"
+        || host.ends_with(".github.com")
+        || host.ends_with(".githubusercontent.com")
+}
+
+/// The URL a redirect from `from` to `location` leads to.
+fn redirect_target(from: &Uri, location: &str) -> Result<Uri> {
+    let target = if location.starts_with('/') && !location.starts_with("//") {
+        let authority = from.authority().map(|a| a.as_str()).unwrap_or_default();
+        let scheme = from.scheme_str().unwrap_or("https");
+        format!("{scheme}://{authority}{location}")
+    } else {
+        location.to_string()
+    };
+    target
+        .parse()
+        .with_context(|| format!("Invalid redirect location '{location}'"))
+}
+
 // -----------------------------------------------------------------------------
 // Repository Info
 // -----------------------------------------------------------------------------
@@ -169,11 +753,66 @@ pub async fn get_current_login(client: &Octocrab) -> Result<String> {
     Ok(user.login)
 }
 
+/// Response to the default-branch GraphQL query.
+#[derive(Debug, Deserialize)]
+struct DefaultBranchResponse {
+    data: Option<DefaultBranchData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DefaultBranchData {
+    repository: Option<DefaultBranchRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DefaultBranchRepository {
+    default_branch_ref: Option<DefaultBranchRef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DefaultBranchRef {
+    name: String,
+}
+
+/// Request headers asking GitHub not to answer from a cache, for `refresh`.
+fn no_cache_headers(refresh: bool) -> Option<HeaderMap> {
+    refresh.then(|| HeaderMap::from_iter([(CACHE_CONTROL, HeaderValue::from_static("no-cache"))]))
+}
+
 /// Get the default branch for a repository.
-pub async fn get_default_branch(client: &Octocrab, owner: &str, repo: &str) -> Result<String> {
-    let repository = client
-        .repos(owner, repo)
-        .get()
+///
+/// Asks GraphQL for just the branch name, falling back to the REST
+/// repository object if that query fails.  With `refresh`, the REST request
+/// bypasses GitHub's response caching.
+pub async fn get_default_branch(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    refresh: bool,
+) -> Result<String> {
+    let query = serde_json::json!({
+        "query": "query($owner: String!, $name: String!) { \
+                  repository(owner: $owner, name: $name) { defaultBranchRef { name } } }",
+        "variables": { "owner": owner, "name": repo },
+    });
+    if let Ok(response) = client.graphql::<DefaultBranchResponse>(&query).await
+        && let Some(branch) = response
+            .data
+            .and_then(|d| d.repository)
+            .and_then(|r| r.default_branch_ref)
+    {
+        return Ok(branch.name);
+    }
+
+    #[derive(Deserialize)]
+    struct Repository {
+        default_branch: Option<String>,
+    }
+
+    let route = format!("/repos/{owner}/{repo}");
+    let repository: Repository = client
+        .get_with_headers(&route, None::<&()>, no_cache_headers(refresh))
         .await
         .context("Failed to fetch repository")?;
 
@@ -182,6 +821,76 @@ pub async fn get_default_branch(client: &Octocrab, owner: &str, repo: &str) -> R
         .context("Repository has no default branch")
 }
 
+/// Check whether a branch, tag, or commit SHA exists in a repository.
+///
+/// Returns `Ok(false)` only when GitHub says the ref doesn't resolve.
+pub async fn ref_exists(client: &Octocrab, owner: &str, repo: &str, git_ref: &str) -> Result<bool> {
+    let route = format!("/repos/{owner}/{repo}/commits/{}", encode_ref(git_ref));
+    let response = client._get(route).await.context("Failed to look up ref")?;
+    match response.status().as_u16() {
+        200..=299 => Ok(true),
+        404 | 422 => Ok(false),
+        status => bail!("Failed to look up ref '{git_ref}': HTTP {status}"),
+    }
+}
+
+/// Characters of a ref escaped in a route: all but unreserved ones and `/`,
+/// which branch names use and the routes above accept as-is.
+const REF_ESCAPES: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Percent-encode a ref for use in a route, so e.g. `#` or `?` in a tag name
+/// can't end the path.
+fn encode_ref(git_ref: &str) -> String {
+    utf8_percent_encode(git_ref, REF_ESCAPES).to_string()
+}
+
+/// How far apart two refs are.
+#[derive(Debug, Deserialize)]
+pub struct Comparison {
+    /// Commits on the head ref that the base lacks
+    pub ahead_by: u64,
+    /// Commits on the base ref that the head lacks
+    pub behind_by: u64,
+}
+
+/// Compare `head` against `base` (branches, tags or SHAs).
+pub async fn compare_refs(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+) -> Result<Comparison> {
+    // Only the counts are needed, not the commits
+    let route = format!(
+        "/repos/{owner}/{repo}/compare/{}...{}?per_page=1",
+        encode_ref(base),
+        encode_ref(head)
+    );
+    client
+        .get(&route, None::<&()>)
+        .await
+        .with_context(|| format!("Failed to compare '{head}' with '{base}'"))
+}
+
+/// List the names of a repository's deployment environments.
+///
+/// Used to offer choices for `type: environment` workflow inputs.
+pub async fn get_environments(client: &Octocrab, owner: &str, repo: &str) -> Result<Vec<String>> {
+    let route = format!("/repos/{owner}/{repo}/environments?per_page=100");
+
+    let response: EnvironmentsResponse = client
+        .get(&route, None::<&()>)
+        .await
+        .context("Failed to fetch environments")?;
+    Ok(response.environments.into_iter().map(|e| e.name).collect())
+}
+
 // -----------------------------------------------------------------------------
 // Workflow Schema
 // -----------------------------------------------------------------------------
@@ -189,28 +898,53 @@ pub async fn get_default_branch(client: &Octocrab, owner: &str, repo: &str) -> R
 /// Fetch and parse a workflow file to extract its input schema.
 ///
 /// Retrieves the workflow YAML from GitHub and parses the `workflow_dispatch.inputs`
-/// section to determine what inputs the workflow accepts.
+/// section to determine what inputs the workflow accepts.  With `refresh`, the
+/// request bypasses GitHub's response caching so a just-pushed change is seen.
 pub async fn get_workflow_schema(
     client: &Octocrab,
     owner: &str,
     repo: &str,
     workflow: &str,
+    refresh: bool,
 ) -> Result<WorkflowSchema> {
     let path = format!(".github/workflows/{workflow}");
+    let yaml_content = get_workflow_file(client, owner, repo, &path, refresh).await?;
+    parse_workflow_schema(&yaml_content)
+}
 
-    let content = client
-        .repos(owner, repo)
-        .get_content()
-        .path(&path)
-        .send()
+/// Fetch the YAML of the workflow file at `path` on the default branch.
+async fn get_workflow_file(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    path: &str,
+    refresh: bool,
+) -> Result<String> {
+    #[derive(Deserialize)]
+    struct File {
+        #[serde(rename = "type")]
+        kind: String,
+        content: Option<String>,
+    }
+
+    let route = format!("/repos/{owner}/{repo}/contents/{path}");
+    let response: serde_json::Value = client
+        .get_with_headers(&route, None::<&()>, no_cache_headers(refresh))
         .await
         .context("Failed to fetch workflow file")?;
 
-    let file = content
-        .items
-        .into_iter()
-        .next()
-        .context("No content returned")?;
+    // The contents API lists a directory's entries as an array
+    if response.is_array() {
+        bail!("Workflow path {path} is a directory, not a workflow file");
+    }
+    let file: File =
+        serde_json::from_value(response).context("Unexpected response for workflow file")?;
+    if file.kind != "file" {
+        bail!(
+            "Workflow path {path} is a {}, not a workflow file",
+            file.kind
+        );
+    }
 
     let encoded = file.content.context("Workflow file has no content")?;
 
@@ -219,8 +953,27 @@ pub async fn get_workflow_schema(
     let decoded = general_purpose::STANDARD
         .decode(&cleaned)
         .context("Failed to decode base64")?;
-    let yaml_content = String::from_utf8(decoded).context("Workflow is not valid UTF-8")?;
+    String::from_utf8(decoded).context("Workflow is not valid UTF-8")
+}
+
+/// List the workflows defined in a repository.
+pub async fn list_workflows(client: &Octocrab, owner: &str, repo: &str) -> Result<Vec<WorkFlow>> {
+    let page = client
+        .workflows(owner, repo)
+        .list()
+        .per_page(100)
+        .send()
+        .await
+        .context("Failed to list workflows")?;
+    Ok(page.items)
+}
 
+/// Read and parse a workflow file from local disk instead of GitHub.
+///
+/// Lets the prompt flow run without network access.
+pub fn read_workflow_schema(path: &Path) -> Result<WorkflowSchema> {
+    let yaml_content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read workflow file {path:?}"))?;
     parse_workflow_schema(&yaml_content)
 }
 
@@ -235,17 +988,48 @@ fn parse_workflow_schema(yaml_content: &str) -> Result<WorkflowSchema> {
         .unwrap_or("Unnamed workflow")
         .to_string();
 
+    // `on` may be a single event, a list of events, or a map of event config
+    let dispatchable = match yaml.get("on") {
+        Some(Value::String(event)) => event == "workflow_dispatch",
+        Some(Value::Sequence(events)) => events.iter().any(|e| e == "workflow_dispatch"),
+        Some(Value::Mapping(events)) => events.contains_key("workflow_dispatch"),
+        _ => false,
+    };
+
     let inputs_value = yaml
         .get("on")
         .and_then(|on| on.get("workflow_dispatch"))
         .and_then(|wd| wd.get("inputs"));
 
-    let inputs: IndexMap<String, WorkflowInput> = match inputs_value {
-        Some(v) => serde_yaml::from_value(v.clone()).context("Failed to parse inputs")?,
-        None => IndexMap::new(),
-    };
+    // Parse each input on its own so one malformed definition doesn't hide
+    // the rest; an input with no body (`version:`) takes all defaults
+    let mut inputs = IndexMap::new();
+    let mut skipped = Vec::new();
+    if let Some(Value::Mapping(mapping)) = inputs_value {
+        for (key, value) in mapping {
+            let Some(key) = scalar_to_string(key) else {
+                skipped.push(format!("{key:?}: input name is not a string"));
+                continue;
+            };
+            let input = match value {
+                Value::Null => Ok(WorkflowInput::default()),
+                v => serde_yaml::from_value::<WorkflowInput>(v.clone()),
+            };
+            match input {
+                Ok(input) => {
+                    inputs.insert(key, input);
+                }
+                Err(err) => skipped.push(format!("{key}: {err}")),
+            }
+        }
+    }
 
-    Ok(WorkflowSchema { name, inputs })
+    Ok(WorkflowSchema {
+        name,
+        inputs,
+        skipped,
+        dispatchable,
+    })
 }
 
 // -----------------------------------------------------------------------------
@@ -312,23 +1096,189 @@ pub async fn get_latest_run(
         .context("No workflow runs found")
 }
 
+/// Fetch a run's title: the `run-name` set by its workflow.
+///
+/// octocrab's `Run` lacks `display_title`, so this reads it with a raw GET.
+/// Returns `None` when the workflow sets no `run-name`, in which case GitHub
+/// titles the run with the workflow's name.
+pub async fn get_run_title(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run: &Run,
+) -> Result<Option<String>> {
+    #[derive(Deserialize)]
+    struct RunTitle {
+        display_title: Option<String>,
+    }
+
+    let route = format!("/repos/{owner}/{repo}/actions/runs/{}", run.id);
+    let response: RunTitle = client
+        .get(&route, None::<&()>)
+        .await
+        .context("Failed to fetch run")?;
+    Ok(response
+        .display_title
+        .filter(|title| !title.is_empty() && *title != run.name))
+}
+
+/// Find a run started by `parent` completing, via an `on: workflow_run` trigger.
+///
+/// Matches `workflow_run` events on the same commit created after `parent`
+/// finished, of workflows whose `on.workflow_run.workflows` names `parent`'s
+/// workflow.  GitHub takes a few seconds to start triggered runs, so this
+/// polls briefly before concluding there is none.
+pub async fn find_triggered_run(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    parent: &Run,
+) -> Result<Option<Run>> {
+    for _ in 0..TRIGGERED_RUN_ATTEMPTS {
+        tokio::time::sleep(Duration::from_secs(POLL_DELAY)).await;
+
+        let runs = client
+            .workflows(owner, repo)
+            .list_all_runs()
+            .event("workflow_run")
+            .head_sha(parent.head_sha.clone())
+            .per_page(20)
+            .send()
+            .await
+            .context("Failed to list triggered workflow runs")?;
+
+        let mut candidates: Vec<Run> = runs
+            .items
+            .into_iter()
+            .filter(|r| r.id != parent.id && r.created_at >= parent.updated_at)
+            .collect();
+        candidates.sort_by_key(|r| r.created_at);
+        for candidate in candidates {
+            // Runs of other workflows completing on the same commit also
+            // trigger workflow_run events; an unreadable file isn't a match
+            if is_triggered_by(client, owner, repo, &candidate, parent)
+                .await
+                .unwrap_or(false)
+            {
+                return Ok(Some(candidate));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Whether `candidate`'s workflow is triggered by `parent`'s completing.
+async fn is_triggered_by(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    candidate: &Run,
+    parent: &Run,
+) -> Result<bool> {
+    let route = format!(
+        "/repos/{owner}/{repo}/actions/workflows/{}",
+        candidate.workflow_id
+    );
+    let workflow: WorkFlow = client
+        .get(&route, None::<&()>)
+        .await
+        .context("Failed to fetch workflow")?;
+    // workflow_run triggers are read from the default branch's file
+    let yaml = get_workflow_file(client, owner, repo, &workflow.path, false).await?;
+    Ok(workflow_run_sources(&yaml)?.contains(&parent.name))
+}
+
+/// Names of the workflows in a workflow file's `on.workflow_run.workflows`.
+fn workflow_run_sources(yaml_content: &str) -> Result<Vec<String>> {
+    let yaml: Value =
+        serde_yaml::from_str(yaml_content).context("Failed to parse workflow YAML")?;
+    let sources = yaml
+        .get("on")
+        .and_then(|on| on.get("workflow_run"))
+        .and_then(|trigger| trigger.get("workflows"));
+    Ok(match sources {
+        Some(Value::Sequence(names)) => names.iter().filter_map(scalar_to_string).collect(),
+        Some(name) => scalar_to_string(name).into_iter().collect(),
+        None => Vec::new(),
+    })
+}
+
+/// Fetch a workflow run, conditionally on the response remembered in `cache`.
+pub async fn get_run(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+    cache: Option<&mut ResponseCache>,
+) -> Result<Run> {
+    let route = format!("/repos/{owner}/{repo}/actions/runs/{run_id}");
+    get_json(client, &route, cache)
+        .await
+        .context("Failed to fetch workflow run")
+}
+
 /// Fetch jobs for a workflow run via a raw GET.
 ///
 /// We deserialize into our own `Job`/`JobStatus` types rather than octocrab's
 /// so that we can handle statuses like "waiting" that octocrab's enum is missing.
+/// Pages through the results so large matrix runs return every job.  With a
+/// `cache`, each page is a conditional request.
 pub async fn get_run_jobs(
     client: &Octocrab,
     owner: &str,
     repo: &str,
     run_id: RunId,
+    mut cache: Option<&mut ResponseCache>,
 ) -> Result<Vec<Job>> {
-    let route = format!("/repos/{owner}/{repo}/actions/runs/{run_id}/jobs");
+    let mut jobs = Vec::new();
+    for page in 1.. {
+        let route = format!(
+            "/repos/{owner}/{repo}/actions/runs/{run_id}/jobs?per_page={JOBS_PER_PAGE}&page={page}"
+        );
+        let response: JobsResponse = get_json(client, &route, cache.as_deref_mut())
+            .await
+            .context("Failed to fetch jobs")?;
+        let last_page = response.jobs.len() < JOBS_PER_PAGE;
+        jobs.extend(response.jobs);
+        // A short page is the last; a missing total never ends paging early
+        let have_all = response
+            .total_count
+            .is_some_and(|total| jobs.len() >= total);
+        if last_page || have_all {
+            break;
+        }
+    }
+    Ok(jobs)
+}
 
-    let response: JobsResponse = client
-        .get(&route, None::<&()>)
+/// Fetch the plain-text log of a job.
+///
+/// GitHub only serves logs once a job has finished; the API responds with a
+/// redirect to the log file.
+pub async fn get_job_logs(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    job_id: u64,
+) -> Result<String> {
+    let route = format!("/repos/{owner}/{repo}/actions/jobs/{job_id}/logs");
+
+    let response = client
+        ._get(route)
+        .await
+        .context("Failed to fetch job logs")?;
+    let response = client
+        .follow_location_to_data(response)
+        .await
+        .context("Failed to download job logs")?;
+    if !response.status().is_success() {
+        bail!("Failed to fetch job logs: HTTP {}", response.status());
+    }
+    client
+        .body_to_string(response)
         .await
-        .context("Failed to fetch jobs")?;
-    Ok(response.jobs)
+        .context("Failed to read job logs")
 }
 
 /// Fetch annotations for a check run.
@@ -348,3 +1298,355 @@ pub async fn get_annotations(
         .await
         .context("Failed to fetch annotations")
 }
+
+/// Collect the outputs published by a completed workflow run.
+///
+/// The REST API does not expose job `outputs`, so workflows publish values to
+/// downstream dispatches as titled notice annotations:
+/// `echo "::notice title=image_tag::$TAG"`.  Each such annotation becomes an
+/// output named by its title.  Later jobs override earlier ones on name clashes.
+pub async fn get_run_outputs(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: RunId,
+) -> Result<IndexMap<String, String>> {
+    let mut outputs = IndexMap::new();
+
+    for job in get_run_jobs(client, owner, repo, run_id, None).await? {
+        let Some(check_run_id) = check_run_id_from_url(&job.check_run_url) else {
+            continue;
+        };
+        for ann in get_annotations(client, owner, repo, check_run_id).await? {
+            if ann.annotation_level.as_deref() != Some("notice") {
+                continue;
+            }
+            if let (Some(title), Some(message)) = (ann.title, ann.message) {
+                outputs.insert(title, message);
+            }
+        }
+    }
+
+    Ok(outputs)
+}
+
+/// Cancel a workflow run.
+pub async fn cancel_run(client: &Octocrab, owner: &str, repo: &str, run_id: u64) -> Result<()> {
+    client
+        .actions()
+        .cancel_workflow_run(owner, repo, RunId(run_id))
+        .await
+        .context("Failed to cancel workflow run")
+}
+
+// -----------------------------------------------------------------------------
+// Deployment Reviews
+// -----------------------------------------------------------------------------
+
+/// List a run's deployments that are waiting on a required reviewer.
+pub async fn get_pending_deployments(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+) -> Result<Vec<PendingDeployment>> {
+    let route = format!("/repos/{owner}/{repo}/actions/runs/{run_id}/pending_deployments");
+    get_json(client, &route, None)
+        .await
+        .context("Failed to fetch pending deployments")
+}
+
+/// Approve a run's pending deployments to the given environments.
+///
+/// GitHub rejects the request unless the authenticated user is a required
+/// reviewer for every environment.
+pub async fn approve_deployments(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+    environment_ids: &[u64],
+) -> Result<()> {
+    let route = format!("/repos/{owner}/{repo}/actions/runs/{run_id}/pending_deployments");
+    let body = serde_json::json!({
+        "environment_ids": environment_ids,
+        "state": "approved",
+        "comment": "Approved with gh-dispatch",
+    });
+    let _: serde_json::Value = client
+        .post(&route, Some(&body))
+        .await
+        .context("Failed to approve deployment")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve `pages` of jobs (one JSON body per page number) on a local port,
+    /// returning the base URI and a count of the requests made.
+    async fn serve_job_pages(
+        pages: Vec<String>,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let page: usize = request
+                    .split("page=")
+                    .nth(2)
+                    .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+                    .and_then(|n| n.parse().ok())
+                    .unwrap();
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let body = &pages[page - 1];
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, requests)
+    }
+
+    /// Answer every request on a local port with `response`, returning the base
+    /// URI and the requests received.
+    async fn serve_fixed(
+        response: String,
+    ) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                received.lock().unwrap().push(request);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, requests)
+    }
+
+    fn jobs_page(ids: std::ops::Range<u64>, total_count: Option<usize>) -> String {
+        let jobs: Vec<_> = ids
+            .map(|id| {
+                serde_json::json!({
+                    "id": id,
+                    "name": format!("job {id}"),
+                    "status": "completed",
+                    "conclusion": "success",
+                    "started_at": null,
+                    "completed_at": null,
+                    "check_run_url": "",
+                })
+            })
+            .collect();
+        let mut page = serde_json::json!({ "jobs": jobs });
+        if let Some(total) = total_count {
+            page["total_count"] = total.into();
+        }
+        page.to_string()
+    }
+
+    #[test]
+    fn parse_headers_validates_names_and_values() {
+        let valid = IndexMap::from([("X-Team".to_string(), "platform".to_string())]);
+        let headers = parse_headers(&valid).unwrap();
+        assert_eq!(headers[0].0, "x-team");
+        assert_eq!(headers[0].1, "platform");
+
+        let bad_name = IndexMap::from([("X Team".to_string(), "platform".to_string())]);
+        assert!(parse_headers(&bad_name).is_err());
+        let bad_value = IndexMap::from([("X-Team".to_string(), "a\nb".to_string())]);
+        assert!(parse_headers(&bad_value).is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_the_token_only_to_trusted_hosts() {
+        let body = "[apps]\n";
+        let (other, other_requests) = serve_fixed(format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        ))
+        .await;
+        let (github, github_requests) = serve_fixed(format!(
+            "HTTP/1.1 302 Found\r\nLocation: {other}/config.toml\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n"
+        ))
+        .await;
+        let trusted_authority = github.trim_start_matches("http://").to_string();
+
+        let text = fetch_following_redirects(
+            &format!("{github}/config.toml"),
+            "http",
+            |url| {
+                url.authority()
+                    .is_some_and(|a| a.as_str() == trusted_authority)
+            },
+            || Ok("secret".to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(text, body);
+        let github_requests = github_requests.lock().unwrap();
+        assert!(github_requests[0].contains("authorization: bearer secret"));
+        let other_requests = other_requests.lock().unwrap();
+        assert_eq!(other_requests.len(), 1);
+        assert!(!other_requests[0].contains("authorization"));
+    }
+
+    #[test]
+    fn redirects_are_resolved_against_the_current_host() {
+        let from: Uri = "https://github.com/acme/config/raw/main/config.toml"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redirect_target(&from, "/acme/config/raw/v2/config.toml").unwrap(),
+            "https://github.com/acme/config/raw/v2/config.toml"
+        );
+        let target = redirect_target(&from, "https://evil.example/config.toml").unwrap();
+        assert_eq!(target.host(), Some("evil.example"));
+        assert!(!is_github_host(target.host().unwrap()));
+        assert!(is_github_host("raw.githubusercontent.com"));
+        assert!(!is_github_host("github.com.evil.example"));
+    }
+
+    #[test]
+    fn workflow_run_sources_reads_the_trigger() {
+        let sources = workflow_run_sources(
+            "on:\n  workflow_run:\n    workflows: [Build, \"Lint & Test\"]\n    types: [completed]\n",
+        )
+        .unwrap();
+        assert_eq!(sources, ["Build", "Lint & Test"]);
+        let single = workflow_run_sources("on:\n  workflow_run:\n    workflows: Build\n").unwrap();
+        assert_eq!(single, ["Build"]);
+        let none = workflow_run_sources("on:\n  push:\n    branches: [main]\n").unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn encode_ref_escapes_route_characters() {
+        assert_eq!(encode_ref("feature/login-v2.1"), "feature/login-v2.1");
+        assert_eq!(encode_ref("release#1 ?x"), "release%231%20%3Fx");
+        assert_eq!(encode_ref("héllo"), "h%C3%A9llo");
+    }
+
+    #[test]
+    fn upload_base_uri_follows_the_host() {
+        assert_eq!(upload_base_uri("github.com"), "https://uploads.github.com");
+        assert_eq!(
+            upload_base_uri("acme.ghe.com"),
+            "https://uploads.acme.ghe.com"
+        );
+        assert_eq!(
+            upload_base_uri("github.acme.corp"),
+            "https://github.acme.corp/api/uploads"
+        );
+    }
+
+    #[test]
+    fn parse_workflow_schema_ignores_unknown_input_keys() {
+        let schema = parse_workflow_schema(
+            r#"
+name: Deploy
+on:
+  workflow_dispatch:
+    inputs:
+      version:
+        description: Version to deploy
+        required: true
+        deprecationMessage: Use tag instead
+        x-custom: [1, 2]
+      environment:
+        type: choice
+        options: [staging, production]
+        default: staging
+        examples:
+          nested: true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(schema.name, "Deploy");
+        assert!(schema.dispatchable);
+        assert!(schema.skipped.is_empty(), "{:?}", schema.skipped);
+        let names: Vec<_> = schema.inputs.keys().map(String::as_str).collect();
+        assert_eq!(names, ["version", "environment"]);
+        assert_eq!(schema.inputs["version"].required, Some(true));
+        let environment = &schema.inputs["environment"];
+        assert_eq!(environment.default.as_deref(), Some("staging"));
+        assert_eq!(
+            environment.options.as_deref(),
+            Some(&["staging".to_string(), "production".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn parse_workflow_schema_skips_only_malformed_inputs() {
+        let schema = parse_workflow_schema(
+            r#"
+on:
+  workflow_dispatch:
+    inputs:
+      good:
+        unknown: value
+        default: 3
+      bad:
+        default: { not: scalar }
+      bare:
+"#,
+        )
+        .unwrap();
+
+        let names: Vec<_> = schema.inputs.keys().map(String::as_str).collect();
+        assert_eq!(names, ["good", "bare"]);
+        assert_eq!(schema.inputs["good"].default.as_deref(), Some("3"));
+        assert_eq!(schema.skipped.len(), 1);
+        assert!(schema.skipped[0].starts_with("bad: "));
+    }
+
+    #[tokio::test]
+    async fn get_run_jobs_pages_without_total_count() {
+        let pages = vec![jobs_page(0..100, None), jobs_page(100..150, None)];
+        let (base, requests) = serve_job_pages(pages).await;
+        let client = Octocrab::builder().base_uri(base).unwrap().build().unwrap();
+
+        let jobs = get_run_jobs(&client, "o", "r", RunId(1), None)
+            .await
+            .unwrap();
+
+        assert_eq!(jobs.len(), 150);
+        assert_eq!(jobs.last().unwrap().id, 149);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn get_run_jobs_stops_at_total_count() {
+        let pages = vec![jobs_page(0..100, Some(100)), jobs_page(100..101, Some(100))];
+        let (base, requests) = serve_job_pages(pages).await;
+        let client = Octocrab::builder().base_uri(base).unwrap().build().unwrap();
+
+        let jobs = get_run_jobs(&client, "o", "r", RunId(1), None)
+            .await
+            .unwrap();
+
+        assert_eq!(jobs.len(), 100);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,56 @@
+//! `pre_dispatch` and `post_dispatch` hook commands.
+//!
+//! Hooks are shell commands from the config, run with `sh -c`.  They learn
+//! about the dispatch from `GH_DISPATCH_*` environment variables: `APP`,
+//! `WORKFLOW`, `REPO`, `REF` and `INPUTS` (JSON, secrets masked), plus
+//! `RUN_ID`, `RUN_URL` and `CONCLUSION` for `post_dispatch` when known.
+//!
+//! A hook's stdout goes to stderr, keeping stdout for `--format json`.
+
+use anyhow::{Context, Result, bail};
+use std::process::{Command, Stdio};
+
+/// Run the `pre_dispatch` hook, failing if it exits nonzero.
+///
+/// The hook's stderr is shown once it finishes, or in the error if it failed.
+pub fn run_pre_dispatch(command: &str, env: &[(&str, String)]) -> Result<()> {
+    let output = hook_command(command, env)
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to run pre_dispatch hook `{command}`"))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            bail!("pre_dispatch hook `{command}` failed ({})", output.status);
+        }
+        bail!(
+            "pre_dispatch hook `{command}` failed ({}): {stderr}",
+            output.status
+        );
+    }
+    eprint!("{stderr}");
+    Ok(())
+}
+
+/// Run the `post_dispatch` hook.  The dispatch has already happened, so a
+/// failure is returned for the caller to report, not to abort on.
+pub fn run_post_dispatch(command: &str, env: &[(&str, String)]) -> Result<()> {
+    let status = hook_command(command, env)
+        .status()
+        .with_context(|| format!("Failed to run post_dispatch hook `{command}`"))?;
+    if !status.success() {
+        bail!("post_dispatch hook `{command}` failed ({status})");
+    }
+    Ok(())
+}
+
+/// Build the `sh -c` command for a hook, with its environment.
+fn hook_command(command: &str, env: &[(&str, String)]) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command])
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::inherit())
+        .stdout(std::io::stderr());
+    cmd
+}
@@ -0,0 +1,258 @@
+//! Workflow inputs supplied outside the interactive prompts.
+//!
+//! Converts values given on the command line, piped to stdin, or edited in
+//! `$EDITOR` into the strings sent with the dispatch, checking them against
+//! the workflow's input schema, and runs the commands that print configured
+//! input values.
+
+use anyhow::{Context, Result, bail};
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::github::WorkflowInput;
+
+// -----------------------------------------------------------------------------
+// Coercion
+// -----------------------------------------------------------------------------
+
+/// Convert a JSON inputs object into dispatch values.
+///
+/// Every key must be an input declared by the workflow.  Values are coerced
+/// to the string form GitHub expects for the input's type.
+pub fn coerce_json_inputs(
+    json: &Map<String, Value>,
+    schema: &IndexMap<String, WorkflowInput>,
+) -> Result<IndexMap<String, String>> {
+    json.iter()
+        .map(|(name, value)| {
+            let Some(input) = schema.get(name) else {
+                bail!(
+                    "Unknown input '{name}' (workflow accepts: {})",
+                    input_names(schema)
+                );
+            };
+            Ok((name.clone(), coerce_value(name, value, input)?))
+        })
+        .collect()
+}
+
+/// Parse `key=value` lines (as read by `--input-stdin`) into an inputs object.
+///
+/// Blank lines and lines starting with `#` are skipped.  Values are kept as
+/// strings and coerced like any other JSON input.
+pub fn parse_input_lines(text: &str) -> Result<Map<String, Value>> {
+    let mut inputs = Map::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            bail!(
+                "Invalid input on line {}: expected key=value, got '{line}'",
+                i + 1
+            );
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("Invalid input on line {}: missing key", i + 1);
+        }
+        inputs.insert(key.to_string(), Value::String(value.trim().to_string()));
+    }
+    Ok(inputs)
+}
+
+/// Convert a JSON inputs object into dispatch values without a schema.
+///
+/// Used with `--no-schema`: keys and values are sent as-is, so GitHub is left
+/// to reject unknown or invalid inputs.
+pub fn json_inputs(json: &Map<String, Value>) -> Result<IndexMap<String, String>> {
+    json.iter()
+        .map(|(name, value)| Ok((name.clone(), value_to_string(name, value)?)))
+        .collect()
+}
+
+/// Coerce a single JSON value to the string form of its input type.
+fn coerce_value(name: &str, value: &Value, input: &WorkflowInput) -> Result<String> {
+    let text = value_to_string(name, value)?;
+    validate_value(name, &text, input)?;
+    Ok(text)
+}
+
+/// Convert a JSON value to the string sent with the dispatch.
+fn value_to_string(name: &str, value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Null => bail!("Input '{name}' is null"),
+        // Structured values are passed through as compact JSON strings
+        Value::Array(_) | Value::Object(_) => value.to_string(),
+    })
+}
+
+/// Check that a value is acceptable for the input's declared type.
+pub fn validate_value(name: &str, value: &str, input: &WorkflowInput) -> Result<()> {
+    match input.input_type.as_deref() {
+        Some("boolean") if value != "true" && value != "false" => {
+            bail!("Input '{name}' must be true or false, got '{value}'")
+        }
+        Some("number") if value.parse::<f64>().is_err() => {
+            bail!("Input '{name}' must be a number, got '{value}'")
+        }
+        Some("choice") => {
+            let options = input.options.as_deref().unwrap_or_default();
+            if !options.iter().any(|o| o == value) {
+                bail!(
+                    "Input '{name}' must be one of [{}], got '{value}'",
+                    options.join(", ")
+                );
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Editor
+// -----------------------------------------------------------------------------
+
+/// Open the resolved inputs as TOML in `$VISUAL`/`$EDITOR` for final edits.
+///
+/// The edited values are re-validated against the schema.  Returns `None` if
+/// the file was saved empty, meaning the dispatch should be aborted.
+pub fn edit_inputs(
+    workflow_name: &str,
+    inputs: &IndexMap<String, String>,
+    schema: &IndexMap<String, WorkflowInput>,
+) -> Result<Option<IndexMap<String, String>>> {
+    let content = format!(
+        "# Inputs for '{workflow_name}'. Save an empty file to abort the dispatch.\n{}",
+        toml::to_string(inputs).context("Failed to serialize inputs")?
+    );
+    let file = InputsFile::create(&content)?;
+    run_editor(&file.path)?;
+    let edited = std::fs::read_to_string(&file.path)
+        .with_context(|| format!("Failed to read {:?}", file.path))?;
+    drop(file);
+
+    let is_empty = edited
+        .lines()
+        .all(|l| l.trim().is_empty() || l.trim_start().starts_with('#'));
+    if is_empty {
+        return Ok(None);
+    }
+
+    let table: toml::Table = toml::from_str(&edited).context("Edited inputs are not valid TOML")?;
+    let json = serde_json::to_value(table)?;
+    let Value::Object(map) = json else {
+        bail!("Edited inputs must be a table of key = value pairs");
+    };
+    let edited = coerce_json_inputs(&map, schema)?;
+
+    for (name, input) in schema {
+        let missing = edited.get(name).is_none_or(|v| v.is_empty());
+        if input.required.unwrap_or(false) && missing {
+            bail!("Required input '{name}' has no value");
+        }
+    }
+
+    Ok(Some(edited))
+}
+
+/// The file inputs are edited in.  It holds secret values, so it is created
+/// fresh (never through an existing file or symlink), readable only by the
+/// user, and removed when dropped.
+struct InputsFile {
+    path: PathBuf,
+}
+
+impl InputsFile {
+    fn create(content: &str) -> Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let path = std::env::temp_dir().join(format!(
+            "gh-dispatch-inputs-{}-{nanos}.toml",
+            std::process::id()
+        ));
+        let mut out = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+            .with_context(|| format!("Failed to create {path:?}"))?;
+        let file = InputsFile { path };
+        out.write_all(content.as_bytes())
+            .with_context(|| format!("Failed to write {:?}", file.path))?;
+        Ok(file)
+    }
+}
+
+impl Drop for InputsFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Run the user's editor on `path` and wait for it to exit.
+fn run_editor(path: &std::path::Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Allow editors configured with arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("$EDITOR is empty")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to run editor `{editor}`"))?;
+    if !status.success() {
+        bail!("Editor `{editor}` exited with {status}");
+    }
+    Ok(())
+}
+
+// -----------------------------------------------------------------------------
+// Commands
+// -----------------------------------------------------------------------------
+
+/// Run an input's `command` with `sh -c` and return its trimmed stdout.
+///
+/// The terminal stays attached so secret managers can prompt to unlock.
+/// Fails with the command's stderr if it exits nonzero.
+pub fn run_input_command(name: &str, command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to run command for input '{name}'"))?;
+    if !output.status.success() {
+        bail!(
+            "Command for input '{name}' failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let value = String::from_utf8(output.stdout)
+        .with_context(|| format!("Command for input '{name}' printed invalid UTF-8"))?;
+    Ok(value.trim().to_string())
+}
+
+/// Comma-separated list of declared input names, for error messages.
+fn input_names(schema: &IndexMap<String, WorkflowInput>) -> String {
+    if schema.is_empty() {
+        return "none".to_string();
+    }
+    schema.keys().cloned().collect::<Vec<_>>().join(", ")
+}
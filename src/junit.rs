@@ -0,0 +1,126 @@
+//! JUnit XML reports of watched runs, for `--output-junit`.
+//!
+//! Each watched run becomes a `<testsuite>` and each of its jobs a
+//! `<testcase>`.  Failed jobs carry a `<failure>` listing their annotations;
+//! skipped and cancelled jobs are marked `<skipped/>`.
+
+use anyhow::{Context, Result};
+use chrono::SecondsFormat;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::github::JobConclusion;
+use crate::watcher::WatchedRun;
+
+/// Render one watched run as a `<testsuite>` element named `name`.
+pub fn testsuite(name: &str, watched: &WatchedRun) -> String {
+    let jobs = &watched.jobs;
+    let failures = jobs
+        .iter()
+        .filter(|job| is_failure(job.conclusion.as_ref()))
+        .count();
+    let skipped = jobs
+        .iter()
+        .filter(|job| is_skipped(job.conclusion.as_ref()))
+        .count();
+    let time: i64 = jobs.iter().filter_map(|job| job.duration_seconds()).sum();
+
+    let mut xml = format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" skipped=\"{skipped}\" \
+         time=\"{time}\" timestamp=\"{}\">\n",
+        escape(name),
+        jobs.len(),
+        watched
+            .run
+            .created_at
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+    );
+    let _ = writeln!(
+        xml,
+        "    <properties><property name=\"url\" value=\"{}\"/></properties>",
+        escape(watched.run.html_url.as_str())
+    );
+    for job in jobs {
+        let time = job.duration_seconds().unwrap_or(0);
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{time}\"",
+            escape(&job.name),
+            escape(name)
+        );
+        let conclusion = job.conclusion.as_ref();
+        if is_failure(conclusion) {
+            let message = conclusion.map_or("unknown", JobConclusion::as_str);
+            let details: Vec<String> = watched
+                .annotations
+                .get(&job.id)
+                .into_iter()
+                .flatten()
+                .map(|ann| {
+                    let level = ann.annotation_level.as_deref().unwrap_or("notice");
+                    let title = ann
+                        .title
+                        .as_deref()
+                        .map(|t| format!("{t}: "))
+                        .unwrap_or_default();
+                    let message = ann.message.as_deref().unwrap_or_default();
+                    format!("[{level}] {title}{message}")
+                })
+                .collect();
+            let _ = writeln!(
+                xml,
+                ">\n      <failure message=\"{message}\">{}</failure>\n    </testcase>",
+                escape(&details.join("\n"))
+            );
+        } else if is_skipped(conclusion) {
+            xml.push_str(">\n      <skipped/>\n    </testcase>\n");
+        } else {
+            xml.push_str("/>\n");
+        }
+    }
+    xml.push_str("  </testsuite>\n");
+    xml
+}
+
+/// Write a report of the given `<testsuite>` elements to `path`, replacing it.
+pub fn write_report(path: &Path, suites: &[String]) -> Result<()> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+    for suite in suites {
+        xml.push_str(suite);
+    }
+    xml.push_str("</testsuites>\n");
+    std::fs::write(path, xml)
+        .with_context(|| format!("Failed to write JUnit report {}", path.display()))
+}
+
+/// Whether a job with this conclusion counts as a failed test case.
+fn is_failure(conclusion: Option<&JobConclusion>) -> bool {
+    matches!(
+        conclusion,
+        Some(JobConclusion::Failure | JobConclusion::TimedOut | JobConclusion::ActionRequired)
+    )
+}
+
+/// Whether a job with this conclusion counts as a skipped test case.
+fn is_skipped(conclusion: Option<&JobConclusion>) -> bool {
+    matches!(
+        conclusion,
+        Some(JobConclusion::Skipped | JobConclusion::Cancelled) | None
+    )
+}
+
+/// Escape text for an XML attribute or element.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
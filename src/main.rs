@@ -1,141 +1,1897 @@
+mod audit;
+mod check;
 mod cli;
 mod config;
+mod diagnostics;
+mod discover;
+mod git;
 mod github;
+mod hooks;
+mod inputs;
+mod junit;
+mod markdown;
 mod prompts;
+mod proxy;
 mod ui;
 mod watcher;
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+use audit::{AuditRecord, LoggedRun, append_record, find_run};
+use check::check;
+use chrono::{DateTime, Utc};
 use clap::Parser;
-use cli::Args;
+use cli::{AnnotationFilter, Args, Command, OutputFormat, TimeoutAction};
 use colored::Colorize;
-use config::load_config;
+use config::{
+    Config, Confirmation, TokenSource, WorkflowRef, load_config, load_remote_config, resolve_chain,
+    resolve_pipeline,
+};
+use diagnostics::print_diagnostics;
+use discover::discover;
+use futures::{StreamExt, TryStreamExt, stream};
+use git::repo_from_git;
 use github::{
-    create_client, dispatch_workflow, get_current_login, get_default_branch, get_latest_run,
-    get_workflow_schema,
+    api_base_uri, compare_refs, configure_http, create_client, create_client_for,
+    create_client_for_host, dispatch_workflow, find_triggered_run, get_current_login,
+    get_default_branch, get_environments, get_latest_run, get_run_outputs, get_run_title,
+    get_workflow_schema, read_workflow_schema, ref_exists,
+};
+use hooks::{run_post_dispatch, run_pre_dispatch};
+use indexmap::IndexMap;
+use inputs::{coerce_json_inputs, edit_inputs, json_inputs, parse_input_lines, run_input_command};
+use inquire::{Confirm, InquireError, Select, Text};
+use junit::{testsuite, write_report};
+use markdown::{run_summary, write_summary};
+use octocrab::Octocrab;
+use octocrab::models::workflows::Run;
+use prompts::{collect_workflow_inputs, confirm_with_timeout};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::Duration;
+use ui::{
+    DispatchOutcome, Icons, create_spinner, describe, icons, info, print_inputs, print_result,
+    set_icons, status_line, status_to_stderr, success, utf8_locale, warning,
 };
-use inquire::{Confirm, Select};
-use prompts::collect_workflow_inputs;
-use ui::{create_spinner, info, success, warning};
-use watcher::watch_run;
+use watcher::{WatchOptions, WatchedRun, progress_group, watch_run, watch_run_in};
+
+/// Maximum number of top-level inputs accepted by `workflow_dispatch`.
+const MAX_INPUTS: usize = 10;
+/// Maximum size of the serialized `workflow_dispatch` inputs payload, in characters.
+const MAX_INPUTS_PAYLOAD: usize = 65_535;
+/// Exit code when a run fails to start (e.g. a workflow syntax error).
+const EXIT_STARTUP_FAILURE: u8 = 3;
+/// Exit code when the user presses Ctrl-C at a prompt, as for SIGINT.
+const EXIT_INTERRUPTED: u8 = 130;
+/// Maximum number of `workflow_run`-triggered runs followed with `--follow-triggered`.
+const MAX_FOLLOW_DEPTH: usize = 5;
+/// How long before the dispatch a found run may have been created before it's
+/// suspected of being an older run than the one just dispatched, in seconds.
+const STALE_RUN_SLACK: i64 = 120;
+/// Shown in place of secret input values.
+const SECRET_MASK: &str = "****";
+
+/// Outputs of workflows already run in this invocation, keyed by workflow name.
+type RunOutputs = HashMap<String, IndexMap<String, String>>;
+
+/// A workflow dispatch whose refs and inputs have been resolved and confirmed.
+struct Dispatch<'a> {
+    workflow_name: &'a str,
+    workflow_ref: &'a WorkflowRef,
+    git_refs: Vec<String>,
+    inputs_json: serde_json::Value,
+    /// Inputs with secret values masked, for display and the audit log
+    masked_inputs: IndexMap<String, String>,
+    /// Client for the workflow's repository
+    client: Octocrab,
+}
+
+/// State shared by every workflow dispatched in one invocation.
+struct Session<'a> {
+    client: &'a Octocrab,
+    args: &'a Args,
+    app_name: &'a str,
+    /// Conclusions that make the command fail
+    fail_on: Vec<String>,
+    /// Annotation levels printed while watching
+    annotations: AnnotationFilter,
+    /// Audit log to record each dispatch in
+    audit_log: Option<PathBuf>,
+    /// Warn when a ref is behind the default branch
+    check_behind: bool,
+    /// Base URI of the REST API, for `--print-curl`
+    api_base: String,
+    /// Global hook commands, used by workflows without their own
+    pre_dispatch: Option<String>,
+    post_dispatch: Option<String>,
+    /// `<testsuite>` elements of the runs watched so far, for `--output-junit`
+    junit_suites: &'a Mutex<Vec<String>>,
+    /// Sections of the runs watched so far, for `--output-markdown`
+    markdown_sections: &'a Mutex<Vec<String>>,
+    /// Clients for workflows with their own token, keyed by token source
+    target_clients: Mutex<HashMap<TokenSource, Octocrab>>,
+    /// Authenticated logins, keyed by token source (`None` for the global token)
+    logins: Mutex<HashMap<Option<TokenSource>, String>>,
+}
+
+impl<'a> Session<'a> {
+    /// The same session for another app, for `--app-glob`.
+    fn for_app(&self, app_name: &'a str) -> Session<'a> {
+        Session {
+            client: self.client,
+            args: self.args,
+            app_name,
+            fail_on: self.fail_on.clone(),
+            annotations: self.annotations,
+            audit_log: self.audit_log.clone(),
+            check_behind: self.check_behind,
+            api_base: self.api_base.clone(),
+            pre_dispatch: self.pre_dispatch.clone(),
+            post_dispatch: self.post_dispatch.clone(),
+            junit_suites: self.junit_suites,
+            markdown_sections: self.markdown_sections,
+            target_clients: Mutex::new(HashMap::new()),
+            logins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Client to use for a workflow's repository.
+    ///
+    /// Workflows with their own `token_env`/`token_command` get a dedicated
+    /// client, created once per token source; others use the global client.
+    fn target_client(&self, workflow_ref: &WorkflowRef) -> Result<Octocrab> {
+        let Some(source) = &workflow_ref.token else {
+            return Ok(self.client.clone());
+        };
+        let mut clients = self.target_clients.lock().unwrap();
+        if let Some(client) = clients.get(source) {
+            return Ok(client.clone());
+        }
+        let client = create_client_for(source)?;
+        clients.insert(source.clone(), client.clone());
+        Ok(client)
+    }
+
+    /// Login authenticated by a workflow's token, fetched on first use.
+    ///
+    /// Only needed once something is dispatched, so a `--dry-run` with a
+    /// local `schema_file` never has to reach the API.
+    async fn target_login(&self, workflow_ref: &WorkflowRef) -> Result<String> {
+        if let Some(login) = self.logins.lock().unwrap().get(&workflow_ref.token) {
+            return Ok(login.clone());
+        }
+
+        let client = self.target_client(workflow_ref)?;
+        let login =
+            get_current_login(&client)
+                .await
+                .with_context(|| match &workflow_ref.token {
+                    Some(source) => format!("Failed to authenticate with token from {source}"),
+                    None => "Failed to authenticate".to_string(),
+                })?;
+        self.logins
+            .lock()
+            .unwrap()
+            .insert(workflow_ref.token.clone(), login.clone());
+        Ok(login)
+    }
+
+    /// Watcher options selected on the command line.
+    fn watch_options(&self) -> WatchOptions {
+        WatchOptions {
+            logs: self.args.watch_logs,
+            events: self.args.json_events,
+            wait_for: self.args.wait_for.clone(),
+            max_jobs: self.args.max_jobs,
+            steps: self.args.steps,
+            annotations: self.annotations,
+            on_timeout: self.args.wait_timeout_action,
+            adaptive: self.args.watch_interval_adaptive,
+            fail_fast: self.args.fail_fast,
+            approve: std::io::stdin().is_terminal() && !self.args.json_events,
+        }
+    }
+
+    /// Append a dispatch to the audit log, if one is configured.
+    ///
+    /// `run` is the run found for the dispatch, if it was looked up, and
+    /// `conclusion` its final conclusion, if it was watched to the end.
+    /// Failures are only warned about: the dispatch has already happened.
+    async fn audit(
+        &self,
+        dispatch: &Dispatch<'_>,
+        git_ref: &str,
+        run: Option<&Run>,
+        conclusion: Option<&str>,
+    ) {
+        let Some(path) = &self.audit_log else {
+            return;
+        };
+        let login = self.target_login(dispatch.workflow_ref).await;
+        let workflow_ref = dispatch.workflow_ref;
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            user: login.as_deref().unwrap_or("unknown"),
+            app: self.app_name,
+            workflow: &workflow_ref.workflow,
+            repo: format!("{}/{}", workflow_ref.owner, workflow_ref.repo),
+            git_ref,
+            inputs: &dispatch.masked_inputs,
+            run_id: run.map(|r| r.id.into_inner()),
+            html_url: run.map(|r| r.html_url.to_string()),
+            conclusion,
+        };
+        if let Err(err) = append_record(path, &record) {
+            warning(&format!("{err:#}"));
+        }
+    }
+
+    /// `GH_DISPATCH_*` environment variables describing a dispatch to a hook.
+    fn hook_env(&self, dispatch: &Dispatch<'_>, git_ref: &str) -> Vec<(&'static str, String)> {
+        let workflow_ref = dispatch.workflow_ref;
+        vec![
+            ("GH_DISPATCH_APP", self.app_name.to_string()),
+            ("GH_DISPATCH_WORKFLOW", dispatch.workflow_name.to_string()),
+            (
+                "GH_DISPATCH_REPO",
+                format!("{}/{}", workflow_ref.owner, workflow_ref.repo),
+            ),
+            ("GH_DISPATCH_REF", git_ref.to_string()),
+            (
+                "GH_DISPATCH_INPUTS",
+                serde_json::to_string(&dispatch.masked_inputs).unwrap_or_default(),
+            ),
+        ]
+    }
+
+    /// Run the `pre_dispatch` hook for each ref, if one is configured.
+    fn pre_dispatch(&self, dispatch: &Dispatch<'_>) -> Result<()> {
+        let hook = dispatch.workflow_ref.pre_dispatch.as_ref();
+        let Some(command) = hook.or(self.pre_dispatch.as_ref()) else {
+            return Ok(());
+        };
+        for git_ref in &dispatch.git_refs {
+            run_pre_dispatch(command, &self.hook_env(dispatch, git_ref))?;
+        }
+        Ok(())
+    }
+
+    /// Run the `post_dispatch` hook, if one is configured, with the run and
+    /// its conclusion when known.  Failures are only warned about.
+    fn post_dispatch(
+        &self,
+        dispatch: &Dispatch<'_>,
+        git_ref: &str,
+        run: Option<&Run>,
+        conclusion: Option<&str>,
+    ) {
+        let hook = dispatch.workflow_ref.post_dispatch.as_ref();
+        let Some(command) = hook.or(self.post_dispatch.as_ref()) else {
+            return;
+        };
+        let mut env = self.hook_env(dispatch, git_ref);
+        if let Some(run) = run {
+            env.push(("GH_DISPATCH_RUN_ID", run.id.to_string()));
+            env.push(("GH_DISPATCH_RUN_URL", run.html_url.to_string()));
+        }
+        if let Some(conclusion) = conclusion {
+            env.push(("GH_DISPATCH_CONCLUSION", conclusion.to_string()));
+        }
+        if let Err(err) = run_post_dispatch(command, &env) {
+            warning(&format!("{err:#}"));
+        }
+    }
+
+    /// Whether human-readable status lines should be printed to stdout.
+    fn human(&self) -> bool {
+        self.args.format != OutputFormat::Json && !self.args.json_events
+    }
+}
+
+/// A workflow in the selection prompt: shown by label, resolved by config key.
+struct WorkflowChoice<'a> {
+    key: &'a str,
+    label: &'a str,
+}
+
+impl fmt::Display for WorkflowChoice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label)
+    }
+}
+
+/// An error that exits with a specific status code instead of 1.
+#[derive(Debug)]
+struct ExitError {
+    code: u8,
+    message: String,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ExitError {}
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Args::parse();
-    let config = load_config()?;
-    let client = create_client()?;
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        // Esc or Ctrl-C at a prompt is the user's choice, not a failure
+        Err(ref err) if let Some(cancel) = prompt_cancellation(err) => match cancel {
+            InquireError::OperationInterrupted => {
+                warning("Interrupted");
+                ExitCode::from(EXIT_INTERRUPTED)
+            }
+            _ => {
+                warning("Aborted");
+                ExitCode::SUCCESS
+            }
+        },
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            let code = err
+                .chain()
+                .find_map(|e| e.downcast_ref::<ExitError>())
+                .map_or(1, |e| e.code);
+            ExitCode::from(code)
+        }
+    }
+}
 
-    // Get app from arg or prompt
-    let selected_app = if let Some(app) = &cli.app {
-        if !config.apps.contains_key(app) {
-            bail!("App '{app}' not found in config");
+/// The prompt cancellation (Esc or Ctrl-C) behind `err`, if that's what it is.
+fn prompt_cancellation(err: &anyhow::Error) -> Option<&InquireError> {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<InquireError>())
+        .find(|e| {
+            matches!(
+                e,
+                InquireError::OperationCanceled | InquireError::OperationInterrupted
+            )
+        })
+}
+
+async fn run() -> Result<()> {
+    let mut cli = Args::parse();
+    let json_output = match &cli.command {
+        Some(Command::Check { format, .. } | Command::Discover { format, .. }) => {
+            *format == OutputFormat::Json
         }
-        app.as_str()
+        Some(Command::Info { .. }) => false,
+        None => cli.format == OutputFormat::Json || cli.json_events,
+    };
+    if json_output {
+        status_to_stderr();
+    }
+    // Colors are already disabled by NO_COLOR; also drop them when the stream
+    // carrying human-readable output is piped.  JSON on stdout is never
+    // colored, so then that is stderr.
+    let human_stream_is_terminal = if json_output {
+        std::io::stderr().is_terminal()
     } else {
-        let mut app_names: Vec<&String> = config.apps.keys().collect();
-        app_names.sort();
-        Select::new("Select application:", app_names)
-            .with_help_message("Application to build/deploy")
-            .prompt()?
+        std::io::stdout().is_terminal()
+    };
+    if !human_stream_is_terminal {
+        colored::control::set_override(false);
+    }
+    if cli.input_stdin {
+        let text = std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")?;
+        let mut inputs = parse_input_lines(&text)?;
+        inputs.extend(cli.input_json.take().unwrap_or_default());
+        cli.input_json = Some(inputs);
+    }
+    if let Some(Command::Info { config }) = &cli.command {
+        return print_diagnostics(config.as_deref()).await;
+    }
+    if let Some(Command::Discover {
+        repo,
+        snippet,
+        format,
+    }) = &cli.command
+    {
+        return discover(&create_client()?, repo, snippet.as_deref(), *format).await;
+    }
+    if let Some(Command::Check { config, format }) = &cli.command {
+        let config = match config.as_deref() {
+            Some(url) if url.starts_with("https://") => load_remote_config(url).await?,
+            path => load_config(path.map(Path::new))?,
+        };
+        if !config.http.is_empty() {
+            configure_http(&config.http)?;
+        }
+        let client = create_client()?;
+        let icons = if utf8_locale() {
+            Icons::unicode()
+        } else {
+            Icons::ascii()
+        };
+        set_icons(icons.with_overrides(&config.ui));
+        for message in &config.warnings {
+            warning(message);
+        }
+        return check(&client, &config, *format).await;
+    }
+    let mut api_base = api_base_uri("github.com");
+    // Enterprise host of --repo-from-git's remote, whose token is looked up
+    let mut enterprise_host = None;
+    let config = if cli.repo_from_git {
+        // Any config file is ignored; the remote says which host to use
+        let remote = repo_from_git()?;
+        if !remote.is_github_com() {
+            api_base = api_base_uri(&remote.host);
+            enterprise_host = Some(remote.host.clone());
+        }
+        let workflow = cli.workflow.as_deref().unwrap_or_default();
+        let config = Config::for_workflow(&remote.owner, &remote.repo, workflow)?;
+        // Select the app and workflow by their keys, which may differ from
+        // the -w value once normalized to a filename
+        let (app, workflows) = config.apps.first().expect("one app");
+        cli.app = Some(app.clone());
+        cli.workflow = workflows.workflows.keys().next().cloned();
+        config
+    } else {
+        match cli.config.as_deref() {
+            Some(url) if url.starts_with("https://") => load_remote_config(url).await?,
+            path => load_config(path.map(Path::new))?,
+        }
+    };
+    // The token is only needed from here on, so a broken config is reported
+    // first; the client also picks up the config's proxy
+    if !config.http.is_empty() {
+        configure_http(&config.http)?;
+    }
+    let client = match &enterprise_host {
+        Some(host) => create_client_for_host(host)?,
+        None => create_client()?,
     };
 
-    let app = &config.apps[selected_app];
+    // Nothing has been printed yet; from here on output uses these glyphs
+    let icons = if cli.ascii || !utf8_locale() {
+        Icons::ascii()
+    } else {
+        Icons::unicode()
+    };
+    set_icons(icons.with_overrides(&config.ui));
+    for message in &config.warnings {
+        warning(message);
+    }
+
+    let audit_log = std::env::var_os("GH_DISPATCH_LOG_FILE")
+        .map(PathBuf::from)
+        .or(config.log_file.clone());
+    // Fail now rather than lose the report after the run
+    if let Some(path) = &cli.output_junit {
+        check_writable(path, "JUnit report")?;
+    }
+    if let Some(path) = &cli.output_markdown {
+        check_writable(path, "Markdown summary")?;
+    }
 
-    // Get workflow from arg or prompt
-    let selected_workflow = if let Some(wf) = &cli.workflow {
-        if !app.contains_key(wf) {
-            bail!("Workflow '{wf}' not found for app '{selected_app}'");
+    // A past run's inputs sit beneath --input-json and --input-stdin ones
+    if let Some(run_id) = cli.prefill_from_run {
+        let path = audit_log.as_ref().context(
+            "--prefill-from-run reads the audit log; set log_file or GH_DISPATCH_LOG_FILE",
+        )?;
+        let logged = find_run(path, run_id)?;
+        check_prefill_target(&mut cli, &config, run_id, &logged);
+        let mut inputs = serde_json::Map::new();
+        for (name, value) in logged.inputs {
+            // Masked secrets weren't logged; prompt for them or use config
+            if value != SECRET_MASK {
+                inputs.insert(name, serde_json::Value::String(value));
+            }
         }
-        wf.clone()
+        inputs.extend(cli.input_json.take().unwrap_or_default());
+        cli.input_json = Some(inputs);
+    }
+
+    // An unknown app or workflow given on the command line falls back to the
+    // selection prompt when there is someone to answer it
+    let interactive = std::io::stdin().is_terminal();
+
+    let glob_apps = match &cli.app_glob {
+        Some(pattern) => {
+            let workflow = cli.workflow.as_deref().unwrap_or_default();
+            let human = cli.format != OutputFormat::Json && !cli.json_events;
+            let Some(apps) = match_apps(&config, pattern, workflow, interactive && human)? else {
+                return Ok(());
+            };
+            // Inputs and checks below are resolved against the first app
+            cli.app = Some(apps[0].to_string());
+            Some(apps)
+        }
+        None => None,
+    };
+
+    // Get app from arg or prompt
+    let selected_app = match cli.app.as_deref() {
+        Some(app) if config.apps.contains_key(app) => app,
+        requested => {
+            if let Some(app) = requested {
+                if !interactive {
+                    bail!("App '{app}' not found in config");
+                }
+                warning(&format!("App '{app}' not found in config"));
+            }
+            let mut app_names: Vec<&String> = config.apps.keys().collect();
+            app_names.sort();
+            Select::new("Select application:", app_names)
+                .with_help_message("Application to build/deploy")
+                .prompt()?
+        }
+    };
+
+    let app = &config.apps[selected_app];
+
+    // Resolve the workflows to run: a pipeline's stages, or a single workflow.
+    // Workflows referenced via `inputs_from` run first, in dependency order.
+    let plan = if let Some(pipeline) = &cli.pipeline {
+        resolve_pipeline(app, pipeline)?
     } else {
-        let workflow_names: Vec<&String> = app.keys().collect();
-        Select::new("Select workflow:", workflow_names)
-            .prompt()?
-            .to_string()
+        let selected_workflow = match cli.workflow.as_deref() {
+            Some(wf) if app.workflows.contains_key(wf) => wf.to_string(),
+            requested => {
+                if let Some(wf) = requested {
+                    if !interactive {
+                        bail!("Workflow '{wf}' not found for app '{selected_app}'");
+                    }
+                    warning(&format!(
+                        "Workflow '{wf}' not found for app '{selected_app}'"
+                    ));
+                }
+                let choices: Vec<WorkflowChoice> = app
+                    .workflows
+                    .iter()
+                    .map(|(key, wf)| WorkflowChoice {
+                        key,
+                        label: wf.display_name(key),
+                    })
+                    .collect();
+                Select::new("Select workflow:", choices)
+                    .prompt()?
+                    .key
+                    .to_string()
+            }
+        };
+        resolve_chain(app, &selected_workflow)?
+    };
+    if plan.len() > 1 && cli.git_ref.len() > 1 {
+        bail!("Multiple --ref values can only be used with a single workflow");
+    }
+    if plan.len() > 1 && cli.wait_for.is_some() {
+        bail!("--wait-for can only be used with a single workflow");
+    }
+
+    let junit_suites = Mutex::new(Vec::new());
+    let markdown_sections = Mutex::new(Vec::new());
+    let session = Session {
+        client: &client,
+        args: &cli,
+        app_name: selected_app,
+        fail_on: if cli.fail_on.is_empty() {
+            config.fail_on.clone()
+        } else {
+            cli.fail_on.clone()
+        },
+        annotations: cli.annotations.unwrap_or(config.annotations),
+        audit_log,
+        check_behind: cli.check_behind || config.check_behind,
+        api_base,
+        pre_dispatch: config.pre_dispatch.clone(),
+        post_dispatch: config.post_dispatch.clone(),
+        junit_suites: &junit_suites,
+        markdown_sections: &markdown_sections,
+        target_clients: Mutex::new(HashMap::new()),
+        logins: Mutex::new(HashMap::new()),
     };
 
-    let workflow_ref = &app[&selected_workflow];
+    if let Some(apps) = glob_apps {
+        let sessions: Vec<Session> = apps.iter().map(|app| session.for_app(app)).collect();
+        return dispatch_apps(&config, &sessions, &plan[plan.len() - 1]).await;
+    }
+
+    if let Some(interval) = cli.loop_interval {
+        let [name] = plan.as_slice() else {
+            bail!("--loop can only be used with a single workflow");
+        };
+        return run_loop(&session, name, &app.workflows[name], interval).await;
+    }
+
+    let mut outputs = RunOutputs::new();
+
+    for (i, name) in plan.iter().enumerate() {
+        let is_last = i + 1 == plan.len();
+        let workflow_ref = &app.workflows[name];
+
+        if plan.len() > 1 && session.human() {
+            info(&format!(
+                "Stage {}/{}: {}",
+                i + 1,
+                plan.len(),
+                workflow_ref.display_name(name).bold()
+            ));
+        }
+
+        let result = run_workflow(
+            &session,
+            name,
+            workflow_ref,
+            &outputs,
+            is_last,
+            !is_last || !cli.no_wait,
+        )
+        .await
+        .and_then(|run| match run {
+            Some(run) if !is_last && run.conclusion.as_deref() != Some("success") => {
+                bail!("Workflow '{name}' did not succeed")
+            }
+            other => Ok(other),
+        });
+
+        let run = match result {
+            Ok(Some(run)) => run,
+            Ok(None) => break,
+            Err(err) if plan.len() > 1 => {
+                print_stage_summary(&plan, i);
+                return Err(err.context(format!(
+                    "Stage '{name}' ({}/{}) failed",
+                    i + 1,
+                    plan.len()
+                )));
+            }
+            Err(err) => return Err(err),
+        };
+
+        if !is_last {
+            let spinner = create_spinner("Reading workflow outputs...");
+            let target_client = session.target_client(workflow_ref)?;
+            let run_outputs = get_run_outputs(
+                &target_client,
+                &workflow_ref.owner,
+                &workflow_ref.repo,
+                run.id,
+            )
+            .await?;
+            spinner.finish_and_clear();
+            outputs.insert(name.clone(), run_outputs);
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve `--app-glob` to the apps it matches, which must all have
+/// `workflow`, and list them for the user to confirm when `confirm` is set.
+///
+/// Returns `None` if the user declined.
+fn match_apps<'c>(
+    config: &'c Config,
+    pattern: &str,
+    workflow: &str,
+    confirm: bool,
+) -> Result<Option<Vec<&'c str>>> {
+    let apps = config.apps_matching(pattern);
+    if apps.is_empty() {
+        bail!("--app-glob '{pattern}' matched no apps");
+    }
+    let missing: Vec<&str> = apps
+        .iter()
+        .copied()
+        .filter(|app| !config.apps[*app].workflows.contains_key(workflow))
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "Workflow '{workflow}' not found for app(s) matching '{pattern}': {}",
+            missing.join(", ")
+        );
+    }
+
+    if confirm {
+        info(&format!(
+            "'{pattern}' matched {} app(s): {}",
+            apps.len(),
+            apps.join(", ").bold()
+        ));
+        if !Confirm::new(&format!("Dispatch '{workflow}' for these apps?"))
+            .with_default(true)
+            .prompt()?
+        {
+            warning("Aborted");
+            return Ok(None);
+        }
+    }
+    Ok(Some(apps))
+}
 
+/// Prompt for inputs, dispatch a single workflow, and optionally watch it.
+///
+/// Command-line inputs apply only when `selected` (the workflow the user
+/// asked for, rather than an upstream one).  Returns the completed run when
+/// watched, or `None` if the user aborted or `wait` is false.  Bails if the
+/// run fails.
+async fn run_workflow(
+    session: &Session<'_>,
+    workflow_name: &str,
+    workflow_ref: &WorkflowRef,
+    upstream: &RunOutputs,
+    selected: bool,
+    wait: bool,
+) -> Result<Option<Run>> {
+    let Some(dispatch) =
+        prepare_dispatch(session, workflow_name, workflow_ref, upstream, selected).await?
+    else {
+        return Ok(None);
+    };
+    execute_dispatch(session, &dispatch, wait).await
+}
+
+/// Resolve refs and inputs for a workflow and ask the user to confirm.
+///
+/// Returns `None` if the user declined.
+async fn prepare_dispatch<'a>(
+    session: &Session<'_>,
+    workflow_name: &'a str,
+    workflow_ref: &'a WorkflowRef,
+    upstream: &RunOutputs,
+    selected: bool,
+) -> Result<Option<Dispatch<'a>>> {
+    let client = session.target_client(workflow_ref)?;
     let owner = &workflow_ref.owner;
     let repo = &workflow_ref.repo;
 
-    // Fetch workflow schema + current login in parallel; resolve git ref from config or default branch
+    // Fetch workflow schema (unless --no-schema); resolve git refs from --ref,
+    // config, or default branch
     let spinner = create_spinner("Fetching workflow...");
-    let (schema, login) = tokio::join!(
-        get_workflow_schema(&client, owner, repo, &workflow_ref.workflow),
-        get_current_login(&client),
-    );
-    let schema = schema?;
-    let login = login?;
-    let git_ref = match &workflow_ref.git_ref {
-        Some(r) => r.clone(),
-        None => get_default_branch(&client, owner, repo).await?,
+    let schema_file = session
+        .args
+        .schema_file
+        .as_ref()
+        .filter(|_| selected)
+        .or(workflow_ref.schema_file.as_ref());
+    let schema = match schema_file {
+        _ if session.args.no_schema => None,
+        Some(path) => Some(read_workflow_schema(path)?),
+        None => Some(
+            get_workflow_schema(
+                &client,
+                owner,
+                repo,
+                &workflow_ref.workflow,
+                session.args.refresh,
+            )
+            .await?,
+        ),
+    };
+    let git_refs = if session.args.git_ref.is_empty() {
+        vec![match &workflow_ref.git_ref {
+            Some(r) => r.clone(),
+            None => get_default_branch(&client, owner, repo, session.args.refresh).await?,
+        }]
+    } else {
+        session.args.git_ref.clone()
     };
+    // Catch typos in given refs before prompting.  Only a definite "not found"
+    // fails, so an unreachable API (e.g. an offline --dry-run) doesn't block.
+    let given = !session.args.git_ref.is_empty() || workflow_ref.git_ref.is_some();
+    if given && !session.args.skip_ref_check {
+        for git_ref in &git_refs {
+            if let Ok(false) = ref_exists(&client, owner, repo, git_ref).await {
+                spinner.finish_and_clear();
+                bail!("ref '{git_ref}' not found in {owner}/{repo}");
+            }
+        }
+    }
     spinner.finish_and_clear();
-    info(&format!(
-        "Workflow: '{}' ({})",
-        schema.name.cyan(),
-        git_ref.dimmed()
-    ));
+    if session.human() {
+        info(&format!(
+            "Workflow: '{}' ({})",
+            schema
+                .as_ref()
+                .map_or(&workflow_ref.workflow, |s| &s.name)
+                .cyan(),
+            git_refs.join(", ").dimmed()
+        ));
+    }
+
+    if session.human() {
+        for skipped in schema.iter().flat_map(|s| &s.skipped) {
+            warning(&format!("Skipping malformed workflow input {skipped}"));
+        }
+    }
+
+    // Prefill from config, then upstream workflow outputs, then the command line
+    let mut prefilled = workflow_ref.inputs.clone().unwrap_or_default();
+    let json = session.args.input_json.as_ref().filter(|_| selected);
+    for (name, command) in &workflow_ref.input_commands {
+        // Not needed when the command line sets the input
+        if json.is_none_or(|json| !json.contains_key(name)) {
+            prefilled.insert(name.clone(), run_input_command(name, command)?);
+        }
+    }
+    for (input, source) in &workflow_ref.inputs_from {
+        let Some(value) = upstream
+            .get(&source.workflow)
+            .and_then(|o| o.get(&source.output))
+        else {
+            bail!(
+                "Workflow '{}' did not produce output '{}' (needed for input '{input}')",
+                source.workflow,
+                source.output
+            );
+        };
+        prefilled.insert(input.clone(), value.clone());
+    }
+    if let Some(json) = json {
+        prefilled.extend(match &schema {
+            Some(schema) => coerce_json_inputs(json, &schema.inputs)?,
+            None => json_inputs(json)?,
+        });
+    }
+
+    // Without a schema there is nothing to prompt for or validate against:
+    // the prefilled values are sent as-is
+    let Some(schema) = schema else {
+        // Prompting would be needed, but can't be done without the schema;
+        // `--input-json '{}'` dispatches a workflow that takes no inputs
+        if prefilled.is_empty() && json.is_none() {
+            bail!(
+                "--no-schema can't prompt for inputs and none were given; set them in \
+                 the config or with --input-json (use '{{}}' for a workflow without inputs)"
+            );
+        }
+        return confirm_dispatch(
+            session,
+            workflow_name,
+            workflow_ref,
+            git_refs,
+            prefilled,
+            client,
+        )
+        .await;
+    };
+
+    // Environment inputs are offered the repo's environments; if they can't be
+    // listed, the prompt falls back to free text
+    let needs_environments = schema.inputs.iter().any(|(name, i)| {
+        i.input_type.as_deref() == Some("environment") && !prefilled.contains_key(name)
+    });
+    let environments = if needs_environments {
+        get_environments(&client, owner, repo).await.ok()
+    } else {
+        None
+    };
+
+    if session.human() {
+        for name in &workflow_ref.input_order {
+            if !schema.inputs.contains_key(name) {
+                warning(&format!("input_order names unknown input '{name}'"));
+            }
+        }
+    }
 
     // Collect inputs (prefilled from config, prompt for missing)
-    let inputs = collect_workflow_inputs(&schema.inputs, workflow_ref.inputs.as_ref())?;
+    let mut inputs = collect_workflow_inputs(
+        &schema.inputs,
+        Some(&prefilled),
+        environments.as_deref(),
+        &workflow_ref.input_order,
+    )?;
 
-    println!(
-        "\nRunning '{}' for {} with inputs:",
-        selected_workflow.bold(),
-        selected_app.cyan().bold()
-    );
-    for (key, value) in &inputs {
-        println!("  {} = {}", key.dimmed(), value.yellow());
+    // Final review of every value in $EDITOR
+    if session.args.input_editor {
+        match edit_inputs(workflow_name, &inputs, &schema.inputs)? {
+            Some(edited) => inputs = edited,
+            None => {
+                warning("Aborted (inputs file saved empty)");
+                return Ok(None);
+            }
+        }
     }
-    println!();
 
-    if !Confirm::new("Continue?").with_default(true).prompt()? {
-        warning("Aborted");
-        return Ok(());
+    if session.human() {
+        warn_stale_refs(session, &client, workflow_ref, &git_refs).await;
+    }
+
+    confirm_dispatch(
+        session,
+        workflow_name,
+        workflow_ref,
+        git_refs,
+        inputs,
+        client,
+    )
+    .await
+}
+
+/// Warn about refs that are behind the default branch (with `check_behind`)
+/// or have moved on from `--since-commit`, so stale code isn't dispatched
+/// by mistake.  A failed comparison is only warned about.
+async fn warn_stale_refs(
+    session: &Session<'_>,
+    client: &Octocrab,
+    workflow_ref: &WorkflowRef,
+    git_refs: &[String],
+) {
+    let since = session.args.since_commit.as_deref();
+    if !session.check_behind && since.is_none() {
+        return;
+    }
+    let owner = &workflow_ref.owner;
+    let repo = &workflow_ref.repo;
+
+    let spinner = create_spinner("Comparing refs...");
+    let mut warnings = Vec::new();
+    if session.check_behind {
+        match get_default_branch(client, owner, repo, false).await {
+            Ok(default) => {
+                for git_ref in git_refs.iter().filter(|r| **r != default) {
+                    match compare_refs(client, owner, repo, &default, git_ref).await {
+                        Ok(c) if c.behind_by > 0 => warnings.push(format!(
+                            "'{git_ref}' is {} behind {default}",
+                            plural(c.behind_by, "commit")
+                        )),
+                        Ok(_) => {}
+                        Err(err) => warnings.push(describe(&err)),
+                    }
+                }
+            }
+            Err(err) => warnings.push(describe(&err)),
+        }
+    }
+    if let Some(sha) = since {
+        let short = sha.get(..7).unwrap_or(sha);
+        for git_ref in git_refs {
+            match compare_refs(client, owner, repo, sha, git_ref).await {
+                Ok(c) if c.behind_by > 0 => {
+                    warnings.push(format!("'{git_ref}' no longer contains commit {short}"));
+                }
+                Ok(c) if c.ahead_by > 0 => warnings.push(format!(
+                    "'{git_ref}' has {} since {short}",
+                    plural(c.ahead_by, "new commit")
+                )),
+                Ok(_) => {}
+                Err(err) => warnings.push(describe(&err)),
+            }
+        }
+    }
+    spinner.finish_and_clear();
+    for message in warnings {
+        warning(&message);
+    }
+}
+
+/// `count` followed by `noun`, pluralized with an "s".
+fn plural(count: u64, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+/// Point `--prefill-from-run` at the logged run's app and workflow when none
+/// were given, and warn when the ones given differ from it: its inputs may
+/// not fit another workflow.
+fn check_prefill_target(cli: &mut Args, config: &Config, run_id: u64, logged: &LoggedRun) {
+    if cli.app.is_none() && cli.app_glob.is_none() && config.apps.contains_key(&logged.app) {
+        cli.app = Some(logged.app.clone());
+    }
+    let Some(app) = cli.app.as_deref().and_then(|name| config.apps.get(name)) else {
+        return;
+    };
+    let same_app = cli.app.as_deref() == Some(logged.app.as_str());
+    if same_app && cli.workflow.is_none() && cli.pipeline.is_none() {
+        let mut keys = app
+            .workflows
+            .iter()
+            .filter(|(_, workflow)| workflow.workflow == logged.workflow)
+            .map(|(key, _)| key);
+        if let (Some(key), None) = (keys.next(), keys.next()) {
+            cli.workflow = Some(key.clone());
+        }
+    }
+    let workflow = cli
+        .workflow
+        .as_deref()
+        .and_then(|key| app.workflows.get(key));
+    if !same_app || workflow.is_some_and(|w| w.workflow != logged.workflow) {
+        warning(&format!(
+            "Run {run_id} was dispatched for {} ({}); its inputs may not fit this workflow",
+            logged.app, logged.workflow
+        ));
+    }
+}
+
+/// Show the resolved inputs and ask the user to confirm the dispatch.
+///
+/// Returns `None` for a dry run or if the user declined.
+async fn confirm_dispatch<'a>(
+    session: &Session<'_>,
+    workflow_name: &'a str,
+    workflow_ref: &'a WorkflowRef,
+    git_refs: Vec<String>,
+    inputs: IndexMap<String, String>,
+    client: Octocrab,
+) -> Result<Option<Dispatch<'a>>> {
+    let format = session.args.format;
+
+    if session.human() {
+        println!(
+            "\nRunning '{}' for {} with inputs:",
+            workflow_ref.display_name(workflow_name).bold(),
+            session.app_name.cyan().bold()
+        );
+    }
+    // Mask secret values so they never reach scrollback, CI logs, or the
+    // audit log
+    let secret_names: Vec<&str> = inputs
+        .keys()
+        .filter(|name| session.args.hide_inputs || workflow_ref.secret_inputs.contains(name))
+        .map(String::as_str)
+        .collect();
+    let masked_inputs: IndexMap<String, String> = inputs
+        .iter()
+        .map(|(name, value)| {
+            let value = if secret_names.contains(&name.as_str()) {
+                SECRET_MASK
+            } else {
+                value
+            };
+            (name.clone(), value.to_string())
+        })
+        .collect();
+    if !session.args.json_events {
+        print_inputs(&masked_inputs, format)?;
+    }
+    if session.human() {
+        println!();
     }
 
-    // Dispatch workflow
-    let spinner = create_spinner("Dispatching workflow...");
     let inputs_json = serde_json::to_value(&inputs)?;
-    dispatch_workflow(
-        &client,
+    check_input_limits(&inputs_json)?;
+
+    if session.args.print_curl {
+        for git_ref in &git_refs {
+            println!(
+                "{}",
+                format_curl(
+                    &session.api_base,
+                    workflow_ref,
+                    git_ref,
+                    &inputs,
+                    &secret_names
+                )?
+            );
+        }
+        return Ok(None);
+    }
+
+    if session.args.dry_run {
+        if session.human() {
+            info("Dry run: not dispatching");
+        }
+        if format == OutputFormat::Json && !session.args.json_events {
+            for git_ref in &git_refs {
+                print_result(
+                    &DispatchOutcome {
+                        app: session.app_name,
+                        workflow: workflow_name,
+                        git_ref: Some(git_ref),
+                        run_id: None,
+                        run_number: None,
+                        html_url: None,
+                        conclusion: None,
+                        jobs: Vec::new(),
+                        inputs: Some(&masked_inputs),
+                    },
+                    format,
+                )?;
+            }
+        }
+        return Ok(None);
+    }
+
+    let mut proceed = match (workflow_ref.confirm, session.args.confirm_timeout) {
+        (Confirmation::Skip, _) => true,
+        (_, Some(secs)) => {
+            confirm_with_timeout(
+                "Continue?",
+                true,
+                Duration::from_secs(secs),
+                session.args.on_confirm_timeout == TimeoutAction::Proceed,
+            )
+            .await?
+        }
+        (_, None) => Confirm::new("Continue?").with_default(true).prompt()?,
+    };
+    // No timeout here: someone has to type the name
+    if proceed && workflow_ref.confirm == Confirmation::Twice {
+        let prompt = format!("Type the app name ({}) to dispatch:", session.app_name);
+        proceed = Text::new(&prompt).prompt()?.trim() == session.app_name;
+    }
+    if !proceed {
+        warning("Aborted");
+        return Ok(None);
+    }
+
+    Ok(Some(Dispatch {
+        workflow_name,
+        workflow_ref,
+        git_refs,
+        inputs_json,
+        masked_inputs,
+        client,
+    }))
+}
+
+/// Dispatch a prepared workflow on each of its refs, and optionally watch it.
+///
+/// Returns the completed run when a single ref was watched.  Bails if a run
+/// fails.
+async fn execute_dispatch(
+    session: &Session<'_>,
+    dispatch: &Dispatch<'_>,
+    wait: bool,
+) -> Result<Option<Run>> {
+    let format = session.args.format;
+    let Dispatch {
+        workflow_name,
+        workflow_ref,
+        git_refs,
+        client,
+        ..
+    } = dispatch;
+    let owner = &workflow_ref.owner;
+    let repo = &workflow_ref.repo;
+
+    let dispatched_at = send_dispatch(session, dispatch).await?;
+
+    // Wait for completion if requested
+    if !wait {
+        if format == OutputFormat::Plain {
+            success("Workflow dispatched (not waiting for completion)");
+        }
+        for git_ref in git_refs {
+            let result = DispatchOutcome {
+                app: session.app_name,
+                workflow: workflow_name,
+                git_ref: Some(git_ref),
+                run_id: None,
+                run_number: None,
+                html_url: None,
+                conclusion: None,
+                jobs: Vec::new(),
+                inputs: Some(&dispatch.masked_inputs),
+            };
+            print_result(&result, format)?;
+            session.audit(dispatch, git_ref, None, None).await;
+            session.post_dispatch(dispatch, git_ref, None, None);
+        }
+        return Ok(None);
+    }
+
+    if session.human() {
+        success("Workflow dispatched");
+    }
+    if git_refs.len() > 1 {
+        let targets: Vec<FanOutTarget> = git_refs
+            .iter()
+            .map(|git_ref| FanOutTarget {
+                session,
+                dispatch,
+                git_ref,
+                label: git_ref.clone(),
+            })
+            .collect();
+        watch_fan_out(&targets, dispatched_at).await?;
+        return Ok(None);
+    }
+
+    let git_ref = &git_refs[0];
+    let spinner = create_spinner("Finding workflow run...");
+    let login = session.target_login(workflow_ref).await?;
+    let mut run =
+        get_latest_run(client, owner, repo, &workflow_ref.workflow, git_ref, &login).await?;
+    spinner.finish_and_clear();
+
+    // GitHub can be slow to list a new run, leaving an older one as the latest
+    while is_stale_run(&run, dispatched_at) {
+        warn_stale_run(&run, None);
+        let interactive = std::io::stdin().is_terminal() && session.human();
+        if !interactive
+            || !Confirm::new("Look for the new run again?")
+                .with_default(true)
+                .prompt()?
+        {
+            break;
+        }
+        let spinner = create_spinner("Finding workflow run...");
+        run = get_latest_run(client, owner, repo, &workflow_ref.workflow, git_ref, &login).await?;
+        spinner.finish_and_clear();
+    }
+
+    print_run_header(session, client, owner, repo, &run).await;
+    let watched = watch_run(
+        client,
         owner,
         repo,
-        &workflow_ref.workflow,
-        &git_ref,
-        inputs_json,
+        run.id.into_inner(),
+        &session.watch_options(),
     )
-    .await?;
+    .await;
+    let conclusion = watched.as_ref().ok().and_then(WatchedRun::conclusion);
+    session
+        .audit(dispatch, git_ref, Some(&run), conclusion)
+        .await;
+    session.post_dispatch(dispatch, git_ref, Some(&run), conclusion);
+    let completed = watched?;
+    report_run(
+        session,
+        workflow_name,
+        Some(git_ref),
+        Some(&dispatch.masked_inputs),
+        &completed,
+    )?;
+
+    // Follow runs started by this one via `on: workflow_run`
+    if session.args.follow_triggered {
+        let mut current = completed.run.clone();
+        for _ in 0..MAX_FOLLOW_DEPTH {
+            if current.conclusion.as_deref() != Some("success") {
+                break;
+            }
+            let spinner = create_spinner("Looking for triggered workflow runs...");
+            let triggered = find_triggered_run(client, owner, repo, &current).await?;
+            spinner.finish_and_clear();
+            let Some(next) = triggered else {
+                break;
+            };
+
+            if session.human() {
+                info(&format!("Triggered workflow: '{}'", next.name.cyan()));
+            }
+            print_run_header(session, client, owner, repo, &next).await;
+            let watched = watch_run(
+                client,
+                owner,
+                repo,
+                next.id.into_inner(),
+                &session.watch_options(),
+            )
+            .await?;
+            let run = &watched.run;
+            report_run(session, &run.name, Some(&run.head_branch), None, &watched)?;
+            current = watched.run;
+        }
+    }
+
+    Ok(Some(completed.run))
+}
+
+/// Run the `pre_dispatch` hook and dispatch the workflow on each of its refs.
+///
+/// Returns when the dispatches were sent, to tell the new runs from older ones.
+async fn send_dispatch(session: &Session<'_>, dispatch: &Dispatch<'_>) -> Result<DateTime<Utc>> {
+    let Dispatch {
+        workflow_ref,
+        git_refs,
+        inputs_json,
+        client,
+        ..
+    } = dispatch;
+
+    session.pre_dispatch(dispatch)?;
+    let spinner = create_spinner("Dispatching workflow...");
+    let dispatched_at = Utc::now();
+    for git_ref in git_refs {
+        dispatch_workflow(
+            client,
+            &workflow_ref.owner,
+            &workflow_ref.repo,
+            &workflow_ref.workflow,
+            git_ref,
+            inputs_json.clone(),
+        )
+        .await?;
+    }
     spinner.finish_and_clear();
+    Ok(dispatched_at)
+}
 
-    // Wait for completion if requested
-    if cli.no_wait {
-        success("Workflow dispatched (not waiting for completion)");
+/// Dispatch a workflow every `interval` until interrupted with Ctrl-C.
+///
+/// Inputs are collected and confirmed once, then reused for every dispatch.
+/// Failed runs are tallied and the loop keeps going unless `--stop-on-failure`
+/// is set.  Bails at the end if any run failed.
+async fn run_loop(
+    session: &Session<'_>,
+    workflow_name: &str,
+    workflow_ref: &WorkflowRef,
+    interval: Duration,
+) -> Result<()> {
+    let Some(dispatch) = prepare_dispatch(
+        session,
+        workflow_name,
+        workflow_ref,
+        &RunOutputs::new(),
+        true,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let (mut succeeded, mut failed) = (0u32, 0u32);
+    loop {
+        if session.human() {
+            info(&format!("Dispatch #{}", succeeded + failed + 1));
+        }
+
+        let result = tokio::select! {
+            result = execute_dispatch(session, &dispatch, !session.args.no_wait) => result,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        match result {
+            Ok(_) => succeeded += 1,
+            Err(err) => {
+                failed += 1;
+                if session.args.stop_on_failure {
+                    info(&format_tally(succeeded, failed));
+                    return Err(err);
+                }
+                warning(&format!("{err:#}"));
+            }
+        }
+
+        if session.human() {
+            info(&format!(
+                "{} {} next dispatch in {}s (Ctrl-C to stop)",
+                format_tally(succeeded, failed),
+                icons().separator,
+                interval.as_secs()
+            ));
+        }
+        tokio::select! {
+            () = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!();
+    info(&format_tally(succeeded, failed));
+    if failed > 0 {
+        bail!("{failed} of {} runs failed", succeeded + failed);
+    }
+    Ok(())
+}
+
+/// Running tally of loop outcomes, e.g. "3 succeeded · 1 failed".
+fn format_tally(succeeded: u32, failed: u32) -> String {
+    let failed_text = format!("{failed} failed");
+    let failed_text = if failed > 0 {
+        failed_text.red().to_string()
     } else {
-        success("Workflow dispatched");
-        let spinner = create_spinner("Finding workflow run...");
-        let run =
-            get_latest_run(&client, owner, repo, &workflow_ref.workflow, &git_ref, &login).await?;
+        failed_text
+    };
+    format!(
+        "{} {} {}",
+        format!("{succeeded} succeeded").green(),
+        icons().separator,
+        failed_text
+    )
+}
+
+/// One run watched by [`watch_fan_out`]: a dispatch on one of its refs.
+struct FanOutTarget<'a> {
+    session: &'a Session<'a>,
+    dispatch: &'a Dispatch<'a>,
+    git_ref: &'a str,
+    /// Shown on the run's lines, e.g. the ref or the app
+    label: String,
+}
+
+/// Find the latest run of a fan-out target's workflow on its ref.
+async fn find_fan_out_run(target: &FanOutTarget<'_>) -> Result<Run> {
+    let workflow_ref = target.dispatch.workflow_ref;
+    let login = target.session.target_login(workflow_ref).await?;
+    get_latest_run(
+        &target.dispatch.client,
+        &workflow_ref.owner,
+        &workflow_ref.repo,
+        &workflow_ref.workflow,
+        target.git_ref,
+        &login,
+    )
+    .await
+}
+
+/// Find and watch the run of each target, rendered in a shared progress
+/// group, at most `--concurrency` at a time.
+///
+/// Every run is watched to completion and reported; bails afterwards if any
+/// of them failed.
+async fn watch_fan_out(targets: &[FanOutTarget<'_>], dispatched_at: DateTime<Utc>) -> Result<()> {
+    let Some(first) = targets.first() else {
+        return Ok(());
+    };
+    let args = first.session.args;
+    let human = first.session.human();
+    let concurrency = args.concurrency.get();
+
+    let spinner = create_spinner("Finding workflow runs...");
+    let mut runs: Vec<Run> = stream::iter(targets.iter().map(find_fan_out_run))
+        .buffered(concurrency)
+        .try_collect()
+        .await?;
+    spinner.finish_and_clear();
+    // As for a single run, offer to look again for runs GitHub hasn't listed yet
+    loop {
+        let stale: Vec<usize> = (0..runs.len())
+            .filter(|&i| is_stale_run(&runs[i], dispatched_at))
+            .collect();
+        if stale.is_empty() {
+            break;
+        }
+        for &i in &stale {
+            warn_stale_run(&runs[i], Some(&targets[i].label));
+        }
+        let interactive = std::io::stdin().is_terminal() && human;
+        if !interactive
+            || !Confirm::new("Look for the new runs again?")
+                .with_default(true)
+                .prompt()?
+        {
+            break;
+        }
+        let spinner = create_spinner("Finding workflow runs...");
+        let found: Vec<Run> = stream::iter(stale.iter().map(|&i| find_fan_out_run(&targets[i])))
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
         spinner.finish_and_clear();
+        for (i, run) in stale.into_iter().zip(found) {
+            runs[i] = run;
+        }
+    }
+
+    if human {
+        let titles: Vec<_> = stream::iter(targets.iter().zip(&runs).map(|(target, run)| {
+            let workflow_ref = target.dispatch.workflow_ref;
+            get_run_title(
+                &target.dispatch.client,
+                &workflow_ref.owner,
+                &workflow_ref.repo,
+                run,
+            )
+        }))
+        .buffered(concurrency)
+        .collect()
+        .await;
+        for ((target, run), title) in targets.iter().zip(&runs).zip(titles) {
+            info(&format!(
+                "{}: Run #{}{}",
+                target.label.bold(),
+                run.run_number.to_string().cyan(),
+                format_run_title(title.ok().flatten())
+            ));
+            println!("  {}", run.html_url.to_string().underline().blue());
+        }
+        println!();
+    }
+
+    let options = first.session.watch_options();
+    let multi = progress_group(&options);
+    let results: Vec<_> = stream::iter(targets.iter().zip(&runs).map(|(target, run)| {
+        let workflow_ref = target.dispatch.workflow_ref;
+        watch_run_in(
+            &target.dispatch.client,
+            &workflow_ref.owner,
+            &workflow_ref.repo,
+            run.id.into_inner(),
+            &multi,
+            Some(&target.label),
+            &options,
+        )
+    }))
+    .buffered(concurrency)
+    .collect()
+    .await;
+
+    let mut failed = 0;
+    for ((target, run), result) in targets.iter().zip(&runs).zip(results) {
+        let FanOutTarget {
+            session,
+            dispatch,
+            git_ref,
+            label,
+        } = target;
+        let conclusion = result.as_ref().ok().and_then(WatchedRun::conclusion);
+        session
+            .audit(dispatch, git_ref, Some(run), conclusion)
+            .await;
+        session.post_dispatch(dispatch, git_ref, Some(run), conclusion);
+        let outcome = result.and_then(|run| {
+            report_run(
+                session,
+                dispatch.workflow_name,
+                Some(git_ref),
+                Some(&dispatch.masked_inputs),
+                &run,
+            )
+        });
+        if let Err(err) = outcome {
+            failed += 1;
+            if human {
+                warning(&format!("{label}: {err:#}"));
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} of {} runs failed", targets.len());
+    }
+    Ok(())
+}
+
+/// Dispatch `workflow` for every app in `sessions` and watch the runs
+/// together, for `--app-glob`.
+///
+/// Inputs are collected and confirmed for every app before any is
+/// dispatched.
+async fn dispatch_apps(config: &Config, sessions: &[Session<'_>], workflow: &str) -> Result<()> {
+    let mut dispatches = Vec::new();
+    for session in sessions {
+        let app = &config.apps[session.app_name];
+        if resolve_chain(app, workflow)?.len() > 1 {
+            bail!(
+                "--app-glob can't run '{workflow}' for app '{}': it takes inputs from \
+                 other workflows",
+                session.app_name
+            );
+        }
+        if session.human() {
+            info(&format!("App: {}", session.app_name.bold()));
+        }
+        let workflow_ref = &app.workflows[workflow];
+        if let Some(dispatch) =
+            prepare_dispatch(session, workflow, workflow_ref, &RunOutputs::new(), true).await?
+        {
+            dispatches.push((session, dispatch));
+        }
+    }
+
+    // Runs are found by workflow, ref and actor, so two apps dispatching the
+    // same workflow on the same ref couldn't be told apart
+    let mut seen = HashMap::new();
+    for (session, dispatch) in &dispatches {
+        let WorkflowRef {
+            owner,
+            repo,
+            workflow,
+            ..
+        } = dispatch.workflow_ref;
+        for git_ref in &dispatch.git_refs {
+            let key = (owner, repo, workflow, git_ref);
+            if let Some(other) = seen.insert(key, session.app_name) {
+                bail!(
+                    "Apps '{other}' and '{}' both dispatch {owner}/{repo} {workflow} on \
+                     '{git_ref}', so their runs can't be told apart",
+                    session.app_name
+                );
+            }
+        }
+    }
 
-        info(&format!("Run #{}", run.run_number.to_string().cyan()));
+    let Some(first) = sessions.first() else {
+        return Ok(());
+    };
+    if first.args.no_wait {
+        for (session, dispatch) in &dispatches {
+            execute_dispatch(session, dispatch, false).await?;
+        }
+        return Ok(());
+    }
+
+    // The first dispatch is the earliest, so no new run predates it
+    let mut dispatched_at = None;
+    for (session, dispatch) in &dispatches {
+        let at = send_dispatch(session, dispatch).await?;
+        dispatched_at.get_or_insert(at);
+    }
+    let Some(dispatched_at) = dispatched_at else {
+        return Ok(());
+    };
+    if first.human() {
+        success("Workflows dispatched");
+    }
+
+    let targets: Vec<FanOutTarget> = dispatches
+        .iter()
+        .flat_map(|(session, dispatch)| {
+            dispatch.git_refs.iter().map(move |git_ref| FanOutTarget {
+                session,
+                dispatch,
+                git_ref,
+                label: if dispatch.git_refs.len() > 1 {
+                    format!("{}@{git_ref}", session.app_name)
+                } else {
+                    session.app_name.to_string()
+                },
+            })
+        })
+        .collect();
+    watch_fan_out(&targets, dispatched_at).await
+}
+
+/// Whether `run` was created well before the dispatch at `dispatched_at`, so
+/// is likely an earlier run rather than the one just dispatched.
+fn is_stale_run(run: &Run, dispatched_at: DateTime<Utc>) -> bool {
+    run.created_at < dispatched_at - chrono::Duration::seconds(STALE_RUN_SLACK)
+}
+
+/// Warn that `run` may not be the run just dispatched.
+///
+/// A status message, so on stderr with `--format json` or `--json-events`.
+fn warn_stale_run(run: &Run, git_ref: Option<&str>) {
+    let prefix = git_ref.map(|r| format!("{r}: ")).unwrap_or_default();
+    warning(&format!(
+        "{prefix}Run #{} was created at {}, before this dispatch; it may be an older run",
+        run.run_number,
+        run.created_at.format("%H:%M:%S UTC")
+    ));
+}
+
+/// Print the run number, title and URL of a run about to be watched.
+async fn print_run_header(
+    session: &Session<'_>,
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run: &Run,
+) {
+    if session.human() {
+        // The title is a nicety; don't fail the dispatch over it
+        let title = get_run_title(client, owner, repo, run).await.ok().flatten();
+        info(&format!(
+            "Run #{}{}",
+            run.run_number.to_string().cyan(),
+            format_run_title(title)
+        ));
         println!("  {}", run.html_url.to_string().underline().blue());
         println!();
+    }
+}
 
-        let completed = watch_run(&client, owner, repo, run.id.into_inner()).await?;
+/// Format a run's title to follow its run number, if it has one.
+fn format_run_title(title: Option<String>) -> String {
+    title.map_or_else(String::new, |t| format!(" {}", t.bold()))
+}
 
-        let conclusion = completed.conclusion.as_deref().unwrap_or("unknown");
+/// Print the result of a completed run.
+///
+/// Bails if the conclusion is one of the `fail_on` conclusions, or if the run
+/// failed to start.
+fn report_run(
+    session: &Session<'_>,
+    workflow_name: &str,
+    git_ref: Option<&str>,
+    inputs: Option<&IndexMap<String, String>>,
+    watched: &WatchedRun,
+) -> Result<()> {
+    let completed = &watched.run;
+    let format = session.args.format;
+    let conclusion = completed.conclusion.as_deref().unwrap_or("unknown");
+    // --fail-fast stops on a failed job, so a failure always fails the command
+    let fails = session.fail_on.iter().any(|c| c == conclusion)
+        || (session.args.fail_fast && conclusion == "failure");
+    let result = DispatchOutcome {
+        app: session.app_name,
+        workflow: workflow_name,
+        git_ref,
+        run_id: Some(completed.id.into_inner()),
+        run_number: Some(completed.run_number),
+        html_url: Some(completed.html_url.to_string()),
+        // A run left going by `--wait-timeout-action continue` has none yet
+        conclusion: (!watched.timed_out).then_some(conclusion),
+        jobs: watched.jobs.iter().map(Into::into).collect(),
+        inputs,
+    };
+    print_result(&result, format)?;
+    // Reports are rewritten with every run so far, so each run lands in them
+    // even if a later one fails the command
+    let mut name = match git_ref {
+        Some(r) => format!("{workflow_name} ({r})"),
+        None => workflow_name.to_string(),
+    };
+    if session.args.app_glob.is_some() {
+        name = format!("{}/{name}", session.app_name);
+    }
+    if let Some(path) = &session.args.output_junit {
+        let mut suites = session.junit_suites.lock().unwrap();
+        suites.push(testsuite(&name, watched));
+        if let Err(err) = write_report(path, &suites) {
+            warning(&format!("{err:#}"));
+        }
+    }
+    if let Some(path) = &session.args.output_markdown {
+        let mut sections = session.markdown_sections.lock().unwrap();
+        sections.push(run_summary(&name, watched));
+        if let Err(err) = write_summary(path, &sections) {
+            warning(&format!("{err:#}"));
+        }
+    }
+    if watched.timed_out {
+        if format == OutputFormat::Plain && session.human() {
+            info("Stopped watching after 30 minutes; the run is still in progress");
+            println!("  {}", completed.html_url.to_string().underline().blue());
+        }
+        return Ok(());
+    }
+
+    // With --wait-for the conclusion is that job's, not the run's
+    let what = match &session.args.wait_for {
+        Some(job) => format!("Job '{job}'"),
+        None => "Workflow".to_string(),
+    };
+    if format == OutputFormat::Plain && session.human() {
+        // Name the ref only when several refs were dispatched
+        let subject = match git_ref {
+            Some(r) if session.args.git_ref.len() > 1 => format!("{what} on {r}"),
+            _ => what.clone(),
+        };
+        // ...and the app only when several apps were
+        let subject = if session.args.app_glob.is_some() {
+            format!("{}: {subject}", session.app_name)
+        } else {
+            subject
+        };
         match conclusion {
-            "success" => success("Workflow completed successfully"),
-            "failure" => {
-                bail!("Workflow failed");
-            }
-            "cancelled" => warning("Workflow was cancelled"),
-            other => info(&format!("Workflow finished: {other}")),
+            "success" => success(&format!("{subject} completed successfully")),
+            // Reported by the error below
+            "startup_failure" => {}
+            _ if fails => {}
+            "cancelled" => warning(&format!("{subject} was cancelled")),
+            other => info(&format!("{subject} finished: {other}")),
         }
     }
+    if conclusion == "startup_failure" {
+        bail!(ExitError {
+            code: EXIT_STARTUP_FAILURE,
+            message: format!(
+                "Workflow failed to start, which usually means a syntax error in the \
+                 workflow file. See {}",
+                completed.html_url
+            ),
+        });
+    }
+    if fails {
+        if conclusion == "failure" {
+            bail!("{what} failed");
+        }
+        bail!("{what} finished: {conclusion}");
+    }
+
+    Ok(())
+}
+
+/// The `curl` command that makes the same dispatch request as
+/// `dispatch_workflow`, with the token read from `$GITHUB_TOKEN`.
+///
+/// Secret inputs are left out of the command: each is read from an
+/// `INPUT_<NAME>` variable instead, listed in a comment above the command.
+fn format_curl(
+    api_base: &str,
+    workflow_ref: &WorkflowRef,
+    git_ref: &str,
+    inputs: &IndexMap<String, String>,
+    secret_names: &[&str],
+) -> Result<String> {
+    let WorkflowRef {
+        owner,
+        repo,
+        workflow,
+        ..
+    } = workflow_ref;
+    // Secrets become placeholders, swapped for the variables once quoted
+    let placeholder = |i: usize| format!("@@gh-dispatch-secret-{i}@@");
+    let inputs: IndexMap<&str, String> = inputs
+        .iter()
+        .map(
+            |(name, value)| match secret_names.iter().position(|s| s == name) {
+                Some(i) => (name.as_str(), placeholder(i)),
+                None => (name.as_str(), value.clone()),
+            },
+        )
+        .collect();
+    let body = serde_json::to_string(&serde_json::json!({ "ref": git_ref, "inputs": inputs }))?;
+    // Single-quoted for the shell, where only `'` itself needs escaping
+    let mut body = body.replace('\'', r"'\''");
+    let mut note = String::new();
+    for (i, name) in secret_names.iter().enumerate() {
+        let var = input_env_var(name);
+        // Closes the quotes around the JSON for the variable's expansion
+        body = body.replace(&placeholder(i), &format!("'\"${var}\"'"));
+        note.push_str(&format!("# Secret input '{name}' is left out; set {var}\n"));
+    }
+    Ok(format!(
+        "{note}curl -X POST \\\n  \
+         -H 'Accept: application/vnd.github+json' \\\n  \
+         -H \"Authorization: Bearer $GITHUB_TOKEN\" \\\n  \
+         {api_base}/repos/{owner}/{repo}/actions/workflows/{workflow}/dispatches \\\n  \
+         -d '{body}'"
+    ))
+}
+
+/// The variable `--print-curl` reads a secret input from, e.g. `INPUT_API_KEY`
+/// for `api-key`.
+fn input_env_var(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("INPUT_{name}")
+}
+
+/// Check that a report can be written to `path`, before anything is run.
+///
+/// Creates the file if it doesn't exist; an existing file is left as-is
+/// until the report replaces it.
+fn check_writable(path: &Path, report: &str) -> Result<()> {
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map(drop)
+        .with_context(|| format!("Cannot write {report} to {}", path.display()))
+}
+
+/// Reject inputs GitHub would refuse with an opaque 422.
+///
+/// `workflow_dispatch` accepts at most 10 top-level inputs, and the serialized
+/// inputs payload may not exceed 65,535 characters.
+fn check_input_limits(inputs: &serde_json::Value) -> Result<()> {
+    let count = inputs.as_object().map_or(0, |o| o.len());
+    if count > MAX_INPUTS {
+        bail!(
+            "Too many inputs: {count} provided, but GitHub allows at most {MAX_INPUTS} \
+             workflow_dispatch inputs"
+        );
+    }
+
+    let size = inputs.to_string().chars().count();
+    if size > MAX_INPUTS_PAYLOAD {
+        bail!(
+            "Inputs payload too large: {size} characters, but GitHub allows at most \
+             {MAX_INPUTS_PAYLOAD} for workflow_dispatch inputs"
+        );
+    }
 
     Ok(())
 }
+
+/// Print which stages of a multi-stage run completed before `failed` failed.
+fn print_stage_summary(plan: &[String], failed: usize) {
+    status_line("");
+    for (i, name) in plan.iter().enumerate() {
+        let icon = match i.cmp(&failed) {
+            Ordering::Less => icons().success.green().bold(),
+            Ordering::Equal => icons().failure.red().bold(),
+            Ordering::Greater => icons().neutral.dimmed(),
+        };
+        status_line(&format!("  {icon} {name}"));
+    }
+    status_line("");
+}
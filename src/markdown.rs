@@ -0,0 +1,78 @@
+//! Markdown summaries of watched runs, for `--output-markdown`.
+//!
+//! Each watched run becomes a section: a heading linking the run with its
+//! conclusion, a table of its jobs, and the annotations of each job with
+//! their `file:line` locations, ready to paste into a PR comment or ticket.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::github::JobConclusion;
+use crate::watcher::{WatchedRun, format_seconds};
+
+/// Render one watched run as a section headed `name`.
+pub fn run_summary(name: &str, watched: &WatchedRun) -> String {
+    let run = &watched.run;
+    let conclusion = match watched.conclusion() {
+        Some(conclusion) => conclusion,
+        None if watched.timed_out => "still running",
+        None => "unknown",
+    };
+    let mut md = format!(
+        "## {}: [Run #{}]({}) {conclusion}\n\n",
+        escape(name),
+        run.run_number,
+        run.html_url
+    );
+
+    md.push_str("| Job | Duration | Conclusion |\n| --- | ---: | --- |\n");
+    for job in &watched.jobs {
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} |",
+            escape(&job.name),
+            job.duration_seconds()
+                .map_or_else(|| "-".to_string(), format_seconds),
+            job.conclusion.as_ref().map_or("-", JobConclusion::as_str)
+        );
+    }
+
+    for job in &watched.jobs {
+        let annotations = watched.annotations.get(&job.id);
+        let Some(annotations) = annotations.filter(|a| !a.is_empty()) else {
+            continue;
+        };
+        let _ = write!(md, "\n### {}\n\n", escape(&job.name));
+        for ann in annotations {
+            let level = ann.annotation_level.as_deref().unwrap_or("notice");
+            let location = if ann.end_line > ann.start_line {
+                format!("{}:{}-{}", ann.path, ann.start_line, ann.end_line)
+            } else {
+                format!("{}:{}", ann.path, ann.start_line)
+            };
+            let title = ann
+                .title
+                .as_deref()
+                .map(|t| format!("{}: ", escape(t)))
+                .unwrap_or_default();
+            let message = ann.message.as_deref().unwrap_or_default();
+            let _ = writeln!(md, "- **{level}** `{location}` {title}{}", escape(message));
+        }
+    }
+    md
+}
+
+/// Write the given run sections to `path`, replacing it.
+pub fn write_summary(path: &Path, sections: &[String]) -> Result<()> {
+    std::fs::write(path, sections.join("\n"))
+        .with_context(|| format!("Failed to write Markdown summary {}", path.display()))
+}
+
+/// Keep text on one line and from breaking out of a table cell.
+fn escape(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|")
+}
@@ -2,12 +2,24 @@
 //!
 //! Generates prompts based on workflow input schemas, supporting:
 //! - Choice inputs (dropdown selection)
+//! - Environment inputs (selection from the repo's environments)
 //! - Boolean inputs (yes/no confirmation)
 //! - String inputs (text entry with optional default)
+//!
+//! Esc at any input prompt but the first goes back to the previous one, with
+//! its answer kept as the starting value.
+//!
+//! Also provides the timed "Continue?" confirmation used by
+//! `--confirm-timeout`.
 
 use anyhow::{Context, Result};
+use colored::Colorize;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType};
 use indexmap::IndexMap;
-use inquire::{Confirm, Select, Text, validator::ValueRequiredValidator};
+use inquire::{Confirm, InquireError, Select, Text, validator::ValueRequiredValidator};
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
 
 use crate::github::WorkflowInput;
 
@@ -15,78 +27,333 @@ use crate::github::WorkflowInput;
 // Prompt Helpers
 // -----------------------------------------------------------------------------
 
+/// Ask a yes/no question that answers itself with `on_timeout` after
+/// `timeout`.
+///
+/// Reads keys directly instead of through inquire, whose blocking prompt
+/// can't be abandoned cleanly when the time runs out.  Enter picks `default`.
+/// The prompt is drawn on stderr and runs on a blocking thread, so it neither
+/// mixes with piped output nor stalls the runtime.  Without a terminal on
+/// stdin nobody can answer, so `on_timeout` applies straight away.
+pub async fn confirm_with_timeout(
+    message: &str,
+    default: bool,
+    timeout: Duration,
+    on_timeout: bool,
+) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        eprintln!(
+            "{} {message} {}{}",
+            "?".green(),
+            if on_timeout { "Yes" } else { "No" }.cyan(),
+            " (stdin is not a terminal)".dimmed()
+        );
+        return Ok(on_timeout);
+    }
+
+    let prompt = message.to_string();
+    let answer = tokio::task::spawn_blocking(move || {
+        read_timed_answer(&prompt, default, timeout, on_timeout)
+    })
+    .await
+    .context("Confirmation prompt failed")??;
+
+    let proceed = answer.unwrap_or(on_timeout);
+    let mut stderr = std::io::stderr();
+    crossterm::execute!(stderr, Clear(ClearType::CurrentLine))?;
+    eprintln!(
+        "\r{} {message} {}{}",
+        "?".green(),
+        if proceed { "Yes" } else { "No" }.cyan(),
+        if answer.is_none() { " (timed out)" } else { "" }.dimmed()
+    );
+    Ok(proceed)
+}
+
+/// Read a y/n answer in raw mode, counting down to `timeout`.  `None` means
+/// the time ran out.
+fn read_timed_answer(
+    message: &str,
+    default: bool,
+    timeout: Duration,
+    on_timeout: bool,
+) -> Result<Option<bool>> {
+    let hint = if default { "(Y/n)" } else { "(y/N)" };
+    let action = if on_timeout { "proceeding" } else { "aborting" };
+    let deadline = Instant::now() + timeout;
+    let mut stderr = std::io::stderr();
+
+    terminal::enable_raw_mode()?;
+    let answer = (|| -> Result<Option<bool>> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            crossterm::execute!(stderr, Clear(ClearType::CurrentLine))?;
+            eprint!(
+                "\r{} {message} {} {}",
+                "?".green(),
+                hint.dimmed(),
+                format!("[{action} in {}s]", remaining.as_secs() + 1).dimmed()
+            );
+            stderr.flush()?;
+
+            if !event::poll(remaining.min(Duration::from_millis(250)))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Err(InquireError::OperationInterrupted.into());
+                }
+                KeyCode::Char('y' | 'Y') => return Ok(Some(true)),
+                KeyCode::Char('n' | 'N') | KeyCode::Esc => return Ok(Some(false)),
+                KeyCode::Enter => return Ok(Some(default)),
+                _ => {}
+            }
+        }
+    })();
+    terminal::disable_raw_mode()?;
+    answer
+}
+
 /// Prompt for a choice input (dropdown selection).
-fn prompt_choice(label: &str, options: &[String]) -> Result<String> {
+///
+/// The cursor starts on `default` when it is one of the options.
+fn prompt_choice(
+    label: &str,
+    help: Option<&str>,
+    options: &[String],
+    default: Option<&str>,
+) -> Result<String> {
     let prompt = format!("Select {label}:");
-    Ok(Select::new(&prompt, options.to_vec()).prompt()?)
+    let mut select = Select::new(&prompt, options.to_vec());
+
+    let default_index = default.and_then(|d| options.iter().position(|o| o == d));
+    if let Some(index) = default_index {
+        select = select.with_starting_cursor(index);
+    }
+
+    let help = match (help, default_index.and(default)) {
+        (Some(h), Some(d)) => Some(format!("{h} (default: {d})")),
+        (Some(h), None) => Some(h.to_string()),
+        (None, Some(d)) => Some(format!("default: {d}")),
+        (None, None) => None,
+    };
+    if let Some(h) = &help {
+        select = select.with_help_message(h);
+    }
+
+    Ok(select.prompt()?)
 }
 
 /// Prompt for a boolean input (yes/no).
-fn prompt_boolean(label: &str, default: bool) -> Result<String> {
-    Ok(Confirm::new(label)
-        .with_default(default)
-        .prompt()?
-        .to_string())
+fn prompt_boolean(label: &str, help: Option<&str>, default: bool) -> Result<String> {
+    let mut confirm = Confirm::new(label).with_default(default);
+    if let Some(h) = help {
+        confirm = confirm.with_help_message(h);
+    }
+    Ok(confirm.prompt()?.to_string())
 }
 
 /// Prompt for a text input with optional default.
-fn prompt_text(label: &str, default: Option<&str>, required: bool) -> Result<String> {
+///
+/// `initial` pre-fills the editable text, e.g. with an earlier answer.
+fn prompt_text(
+    label: &str,
+    help: Option<&str>,
+    default: Option<&str>,
+    initial: Option<&str>,
+    required: bool,
+) -> Result<String> {
     let prompt = format!("Enter {label}:");
     let mut text = Text::new(&prompt);
+    if let Some(h) = help {
+        text = text.with_help_message(h);
+    }
     if let Some(d) = default {
         text = text.with_default(d);
     }
+    if let Some(i) = initial {
+        text = text.with_initial_value(i);
+    }
     if required {
         text = text.with_validator(ValueRequiredValidator::default());
     }
     Ok(text.prompt()?)
 }
 
+/// Prompt for one input based on its type (choice/environment/boolean/string).
+///
+/// `previous` is the user's earlier answer when they came back to this input;
+/// it takes the place of the schema default.  `can_go_back` adds a hint that
+/// Esc returns to the previous input.
+fn prompt_input(
+    name: &str,
+    input: &WorkflowInput,
+    environments: Option<&[String]>,
+    previous: Option<&str>,
+    can_go_back: bool,
+) -> Result<String> {
+    // Labelled by input name with the description as help text
+    let help = match (input.description.as_deref(), can_go_back) {
+        (Some(d), true) => Some(format!("{d} (esc to go back)")),
+        (None, true) => Some("esc to go back".to_string()),
+        (d, false) => d.map(str::to_string),
+    };
+    let help = help.as_deref();
+    let default = previous.or(input.default.as_deref());
+
+    match input.input_type.as_deref() {
+        Some("choice") => {
+            let options = input
+                .options
+                .as_ref()
+                .context(format!("Choice input '{name}' has no options"))?;
+            prompt_choice(name, help, options, default)
+        }
+        Some("environment") if environments.is_some_and(|e| !e.is_empty()) => {
+            let options = environments.unwrap_or_default();
+            prompt_choice(name, help, options, default)
+        }
+        Some("boolean") => prompt_boolean(name, help, default == Some("true")),
+        _ => {
+            let required = input.required.unwrap_or(false);
+            prompt_text(name, help, input.default.as_deref(), previous, required)
+        }
+    }
+}
+
+/// The prefilled value to use for `name`, or `None` to prompt for it.
+///
+/// An empty value for a required input without a default would be rejected
+/// by GitHub, so it is prompted for instead.
+fn prefilled_value<'a>(
+    prefilled: Option<&'a IndexMap<String, String>>,
+    name: &str,
+    input: &WorkflowInput,
+) -> Option<&'a String> {
+    let required = input.required.unwrap_or(false);
+    prefilled
+        .and_then(|values| values.get(name))
+        .filter(|value| !(value.is_empty() && required && input.default.is_none()))
+}
+
 /// Collect workflow inputs by prompting the user.
 ///
 /// For each input in the schema:
 /// - If a prefilled value exists in config, use it (no prompt)
 /// - Otherwise, prompt based on the input type (choice/boolean/string)
 ///
+/// Inputs named in `order` are prompted first, in that order; the rest
+/// follow in schema order.  Esc goes back to the previous prompted input; at
+/// the first one it aborts.
+///
+/// `environments` lists the repository's deployment environments offered for
+/// `environment` inputs; when `None` those inputs fall back to free text.
+///
 /// Returns an ordered map of input name -> value.
 pub fn collect_workflow_inputs(
     inputs: &IndexMap<String, WorkflowInput>,
     prefilled: Option<&IndexMap<String, String>>,
+    environments: Option<&[String]>,
+    order: &[String],
 ) -> Result<IndexMap<String, String>> {
-    let mut results = IndexMap::new();
-
-    for (name, input) in inputs {
-        // Use prefilled value if available
-        if let Some(prefilled_values) = prefilled
-            && let Some(value) = prefilled_values.get(name)
-        {
-            results.insert(name.clone(), value.clone());
-            continue;
-        }
+    let prefilled_value =
+        |name: &str, input: &WorkflowInput| prefilled_value(prefilled, name, input);
+    let mut to_prompt: Vec<(&String, &WorkflowInput)> = inputs
+        .iter()
+        .filter(|(name, input)| prefilled_value(name, input).is_none())
+        .collect();
+    // Stable, so unlisted inputs keep their relative order after listed ones
+    to_prompt.sort_by_key(|(name, _)| order.iter().position(|o| o == *name).unwrap_or(order.len()));
 
-        // Prompt user based on input type
-        let label = input.description.as_deref().unwrap_or(name);
-        let value = match input.input_type.as_deref() {
-            Some("choice") => {
-                let options = input
-                    .options
-                    .as_ref()
-                    .context(format!("Choice input '{name}' has no options"))?;
-                prompt_choice(label, options)?
+    // Index-based so Esc can step back and re-answer an earlier input
+    let mut answers: IndexMap<&str, String> = IndexMap::new();
+    let mut i = 0;
+    while let Some(&(name, input)) = to_prompt.get(i) {
+        let previous = answers.get(name.as_str()).map(String::as_str);
+        match prompt_input(name, input, environments, previous, i > 0) {
+            Ok(value) => {
+                answers.insert(name, value);
+                i += 1;
             }
-            Some("boolean") => {
-                let default = input.default.as_deref() == Some("true");
-                prompt_boolean(label, default)?
+            Err(err)
+                if i > 0
+                    && matches!(
+                        err.downcast_ref::<InquireError>(),
+                        Some(InquireError::OperationCanceled)
+                    ) =>
+            {
+                i -= 1;
             }
-            _ => {
-                let default = input.default.as_deref();
-                let required = input.required.unwrap_or(false);
-                prompt_text(label, default, required)?
-            }
-        };
+            Err(err) => return Err(err),
+        }
+    }
 
+    let mut results = IndexMap::new();
+    for (name, input) in inputs {
+        let value = match prefilled_value(name, input) {
+            Some(value) => value.clone(),
+            None => answers.swap_remove(name.as_str()).unwrap_or_default(),
+        };
         results.insert(name.clone(), value);
     }
-
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(required: bool, default: Option<&str>) -> WorkflowInput {
+        WorkflowInput {
+            required: Some(required),
+            default: default.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_prefill_for_required_input_is_prompted() {
+        let prefilled = IndexMap::from([("version".to_string(), String::new())]);
+        let required = input(true, None);
+        assert_eq!(
+            prefilled_value(Some(&prefilled), "version", &required),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_prefill_is_kept_when_allowed() {
+        let prefilled = IndexMap::from([("version".to_string(), String::new())]);
+        for allowed in [input(false, None), input(true, Some("1.0"))] {
+            let value = prefilled_value(Some(&prefilled), "version", &allowed);
+            assert_eq!(value.map(String::as_str), Some(""));
+        }
+    }
+
+    #[test]
+    fn fully_prefilled_inputs_are_collected_without_prompting() {
+        let inputs = IndexMap::from([
+            ("version".to_string(), input(true, None)),
+            ("notes".to_string(), input(false, None)),
+        ]);
+        let prefilled = IndexMap::from([
+            ("notes".to_string(), String::new()),
+            ("version".to_string(), "1.2".to_string()),
+        ]);
+        let values = collect_workflow_inputs(&inputs, Some(&prefilled), None, &[]).unwrap();
+        let values: Vec<_> = values
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(values, [("version", "1.2"), ("notes", "")]);
+    }
+}
@@ -1,42 +1,298 @@
 //! Terminal UI helpers.
 //!
 //! Provides styled output functions for consistent CLI feedback:
-//! spinners, success/info/warning messages.
+//! spinners, success/info/warning messages, and rendering of inputs and
+//! results in the selected `--format`.  Status glyphs come from a
+//! process-wide [`Icons`] set: Unicode by default, ASCII with `--ascii` or a
+//! non-UTF-8 locale, with per-glyph overrides from the config's `[ui]` table.
 
+use anyhow::Result;
 use colored::Colorize;
+use indexmap::IndexMap;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+use crate::cli::OutputFormat;
+use crate::config::UiConfig;
+use crate::github::{Job, JobConclusion, JobStatus};
+
+// -----------------------------------------------------------------------------
+// Types
+// -----------------------------------------------------------------------------
+
+/// Final result of a dispatched workflow, as rendered by [`print_result`].
+#[derive(Debug, Serialize)]
+pub struct DispatchOutcome<'a> {
+    pub app: &'a str,
+    pub workflow: &'a str,
+    pub git_ref: Option<&'a str>,
+    pub run_id: Option<u64>,
+    pub run_number: Option<i64>,
+    pub html_url: Option<String>,
+    /// `None` when the run was not watched to completion
+    pub conclusion: Option<&'a str>,
+    /// Jobs of the run; empty when the run was not watched
+    pub jobs: Vec<JobOutcome>,
+    /// Inputs dispatched with, secrets masked; absent for runs not
+    /// dispatched by us (e.g. followed `workflow_run` runs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<&'a IndexMap<String, String>>,
+}
+
+/// Final state of a single job in a watched run.
+#[derive(Debug, Serialize)]
+pub struct JobOutcome {
+    pub name: String,
+    pub status: JobStatus,
+    pub conclusion: Option<JobConclusion>,
+    pub duration_seconds: Option<i64>,
+}
+
+impl From<&Job> for JobOutcome {
+    fn from(job: &Job) -> Self {
+        JobOutcome {
+            name: job.name.clone(),
+            status: job.status.clone(),
+            conclusion: job.conclusion.clone(),
+            duration_seconds: job.duration_seconds(),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Icons
+// -----------------------------------------------------------------------------
+
+/// Glyphs used for status icons and separators.
+#[derive(Debug, Clone)]
+pub struct Icons {
+    pub success: String,
+    pub failure: String,
+    /// Skipped, cancelled, queued, or not yet run
+    pub neutral: String,
+    pub running: String,
+    pub unknown: String,
+    pub info: String,
+    pub warning: String,
+    /// Between items on a summary line
+    pub separator: String,
+    /// Left margin of job log lines
+    pub gutter: String,
+    /// Spinner animation frames; the last is shown when finished
+    pub spinner: String,
+}
+
+impl Icons {
+    /// The default Unicode glyphs.
+    pub fn unicode() -> Self {
+        Icons {
+            success: "✓".into(),
+            failure: "✗".into(),
+            neutral: "○".into(),
+            running: "●".into(),
+            unknown: "?".into(),
+            info: "→".into(),
+            warning: "!".into(),
+            separator: "·".into(),
+            gutter: "│".into(),
+            spinner: "⠁⠂⠄⡀⢀⠠⠐⠈ ".into(),
+        }
+    }
+
+    /// Plain ASCII glyphs for terminals and screen readers without Unicode.
+    pub fn ascii() -> Self {
+        Icons {
+            success: "[OK]".into(),
+            failure: "[X]".into(),
+            neutral: "[.]".into(),
+            running: "[*]".into(),
+            unknown: "[?]".into(),
+            info: "->".into(),
+            warning: "[!]".into(),
+            separator: "-".into(),
+            gutter: "|".into(),
+            spinner: "|/-\\ ".into(),
+        }
+    }
+
+    /// Replace glyphs set in the config's `[ui]` table.
+    pub fn with_overrides(mut self, ui: &UiConfig) -> Self {
+        let overrides = [
+            (&mut self.success, &ui.success),
+            (&mut self.failure, &ui.failure),
+            (&mut self.neutral, &ui.neutral),
+            (&mut self.running, &ui.running),
+            (&mut self.unknown, &ui.unknown),
+            (&mut self.info, &ui.info),
+            (&mut self.warning, &ui.warning),
+            (&mut self.separator, &ui.separator),
+            (&mut self.gutter, &ui.gutter),
+        ];
+        for (glyph, value) in overrides {
+            if let Some(value) = value {
+                glyph.clone_from(value);
+            }
+        }
+        self
+    }
+}
+
+static ICONS: OnceLock<Icons> = OnceLock::new();
+
+/// Set the glyphs used for the rest of the process.
+///
+/// Panics if glyphs were already set or used: output before this call would
+/// have silently used the defaults.
+pub fn set_icons(icons: Icons) {
+    assert!(ICONS.set(icons).is_ok(), "icons were used before being set");
+}
+
+/// The glyphs in use.
+pub fn icons() -> &'static Icons {
+    ICONS.get_or_init(Icons::unicode)
+}
+
+/// Whether the locale (`LC_ALL`, `LC_CTYPE`, or `LANG`) uses UTF-8.
+///
+/// An unset locale is assumed to be UTF-8, as on most modern systems.
+pub fn utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .is_none_or(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
 // -----------------------------------------------------------------------------
 // Output Helpers
 // -----------------------------------------------------------------------------
 
 const TICK_INTERVAL: u64 = 80; // milliseconds
 
+/// Style shared by all spinners: the icon set's frames, then a message
+/// truncated to the terminal width.
+pub fn spinner_style() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .tick_chars(&icons().spinner)
+        .template("{spinner:.cyan} {wide_msg}")
+        .unwrap()
+}
+
 /// Create a spinner with the given message.
 pub fn create_spinner(message: &str) -> ProgressBar {
     let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.cyan} {msg}")
-            .unwrap(),
-    );
+    spinner.set_style(spinner_style());
     spinner.set_message(message.to_string());
     spinner.enable_steady_tick(Duration::from_millis(TICK_INTERVAL));
     spinner
 }
 
+/// Whether status messages go to stderr, leaving stdout to JSON output.
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send the status messages below to stderr from now on, for `--format json`
+/// and `--json-events` whose stdout must stay parseable.
+pub fn status_to_stderr() {
+    STATUS_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Print a line of human-readable status: on stdout, or stderr once
+/// [`status_to_stderr`] was called.
+pub fn status_line(line: &str) {
+    if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
 /// Print a success message with green checkmark.
 pub fn success(msg: &str) {
-    println!("{} {}", "✓".green().bold(), msg);
+    status_line(&format!("{} {}", icons().success.green().bold(), msg));
 }
 
 /// Print an info message with blue arrow.
 pub fn info(msg: &str) {
-    println!("{} {}", "→".blue().bold(), msg);
+    status_line(&format!("{} {}", icons().info.blue().bold(), msg));
 }
 
 /// Print a warning message with yellow exclamation.
 pub fn warning(msg: &str) {
-    println!("{} {}", "!".yellow().bold(), msg);
+    status_line(&format!("{} {}", icons().warning.yellow().bold(), msg));
+}
+
+/// Describe an error by its outermost context and its root cause, leaving out
+/// the API client's own detail (e.g. backtraces) in between.
+pub fn describe(err: &anyhow::Error) -> String {
+    let root = err.root_cause().to_string();
+    let outer = err.to_string();
+    if outer == root {
+        outer
+    } else {
+        format!("{outer}: {root}")
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Formatted Output
+// -----------------------------------------------------------------------------
+
+/// Print resolved workflow inputs in the given format.  JSON output carries
+/// them in the result instead, to stay a single document.
+pub fn print_inputs(inputs: &IndexMap<String, String>, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Plain => {
+            for (key, value) in inputs {
+                println!("  {} = {}", key.dimmed(), value.yellow());
+            }
+        }
+        OutputFormat::Table => {
+            let width = inputs.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+            for (key, value) in inputs {
+                println!(
+                    "  {}  {}",
+                    format!("{key:<width$}").dimmed(),
+                    value.yellow()
+                );
+            }
+        }
+        OutputFormat::Json => {}
+    }
+    Ok(())
+}
+
+/// Print the final result of a run.  Plain output is left to the caller's
+/// status messages.
+pub fn print_result(result: &DispatchOutcome, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Plain => {}
+        OutputFormat::Table => {
+            let rows = [
+                ("App", result.app.to_string()),
+                ("Workflow", result.workflow.to_string()),
+                ("Ref", result.git_ref.unwrap_or("-").to_string()),
+                (
+                    "Run",
+                    result
+                        .run_number
+                        .map_or_else(|| "-".to_string(), |n| format!("#{n}")),
+                ),
+                ("URL", result.html_url.clone().unwrap_or_else(|| "-".into())),
+                (
+                    "Conclusion",
+                    result.conclusion.unwrap_or("not watched").to_string(),
+                ),
+            ];
+            for (key, value) in rows {
+                println!("  {}  {}", format!("{key:<10}").dimmed(), value);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(result)?),
+    }
+    Ok(())
 }
@@ -3,51 +3,346 @@
 //! Polls a workflow run and renders each job as a live spinner inside an
 //! `indicatif::MultiProgress` group.  Completed steps are printed once as
 //! they finish.  Annotations (notices, warnings, errors) are fetched and
-//! displayed when each job completes.  The loop exits when the run reaches
-//! "completed" status.
+//! displayed when each job completes, optionally preceded by the job's log.
+//! The loop exits when the run reaches "completed" status, after printing a
+//! summary table of every job.
+//!
+//! Runs with more than `WatchOptions::max_jobs` jobs are shown condensed: a
+//! single line of job counts instead of a spinner per job.
+//!
+//! With `WatchOptions::events` set, the human UI is replaced by a stream of
+//! newline-delimited JSON events on stdout.
+//!
+//! With `WatchOptions::fail_fast` set, watching stops as soon as any job
+//! fails, reporting the run as failed while its other jobs carry on.
+//!
+//! With `WatchOptions::adaptive` set, the poll interval doubles (up to
+//! `MAX_POLL_INTERVAL`) while nothing about the run changes between polls, and
+//! drops back to `POLL_INTERVAL` as soon as something does.
+//!
+//! Watching stops after 30 minutes; `WatchOptions::on_timeout` decides whether
+//! that fails, cancels the run, or leaves it running.
+//!
+//! Secondary rate limits don't end the watch: the loop waits out GitHub's
+//! `Retry-After` delay, with a warning, and polls again.
+//!
+//! With `WatchOptions::approve` set, a run waiting on a deployment review the
+//! current user may give prompts to approve it.
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use colored::Colorize;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use inquire::Confirm;
 use octocrab::{Octocrab, models::workflows::Run, params::checks::CheckRunAnnotation};
+use serde::Serialize;
 
 use std::collections::{HashMap, HashSet};
+use std::io::Write as _;
 use std::time::Duration;
 
+use crate::cli::{AnnotationFilter, StepFilter, WaitTimeoutAction};
 use crate::github::{
-    Job, JobConclusion, JobStatus, check_run_id_from_url, get_annotations, get_run_jobs,
+    Job, JobConclusion, JobStatus, ResponseCache, SecondaryRateLimit, approve_deployments,
+    cancel_run, check_run_id_from_url, get_annotations, get_job_logs, get_pending_deployments,
+    get_run, get_run_jobs,
 };
+use crate::ui::{icons, spinner_style, status_line};
 
 const POLL_INTERVAL: u64 = 5; // seconds
+const MAX_POLL_INTERVAL: u64 = 30; // seconds, with adaptive polling
 const MAX_WAIT: u64 = 30 * 60; // 30 minutes
 const TICK_INTERVAL: u64 = 80; // milliseconds
 
+/// Options controlling what the watcher prints.
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Print each job's log output when the job completes
+    pub logs: bool,
+    /// Emit newline-delimited JSON events instead of the progress display
+    pub events: bool,
+    /// Stop watching as soon as the job with this name completes
+    pub wait_for: Option<String>,
+    /// Show only job counts for runs with more jobs than this
+    pub max_jobs: Option<usize>,
+    /// Which completed steps to print (or emit as events)
+    pub steps: StepFilter,
+    /// Which annotation levels to print (or emit as events)
+    pub annotations: AnnotationFilter,
+    /// What to do when the run outlasts `MAX_WAIT`
+    pub on_timeout: WaitTimeoutAction,
+    /// Poll less often while the run is idle
+    pub adaptive: bool,
+    /// Stop watching as soon as any job fails
+    pub fail_fast: bool,
+    /// Prompt to approve deployments waiting on the current user's review
+    pub approve: bool,
+}
+
+/// A watched run and its jobs as last seen by the watcher.
+#[derive(Debug)]
+pub struct WatchedRun {
+    pub run: Run,
+    pub jobs: Vec<Job>,
+    /// Watching stopped at the time limit with the run still going
+    /// (`WaitTimeoutAction::Continue`)
+    pub timed_out: bool,
+    /// Annotations of each completed job, keyed by job ID, whether or not
+    /// they were shown
+    pub annotations: HashMap<u64, Vec<CheckRunAnnotation>>,
+}
+
+impl WatchedRun {
+    /// The run's conclusion, or `None` if watching stopped before it finished.
+    pub fn conclusion(&self) -> Option<&str> {
+        if self.timed_out {
+            None
+        } else {
+            self.run.conclusion.as_deref()
+        }
+    }
+}
+
+/// A single event emitted in `--json-events` mode.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    JobStarted {
+        job: &'a str,
+    },
+    StepCompleted {
+        job: &'a str,
+        step: &'a str,
+        conclusion: Option<&'a JobConclusion>,
+    },
+    JobCompleted {
+        job: &'a str,
+        conclusion: Option<&'a JobConclusion>,
+        duration_seconds: Option<i64>,
+    },
+    Annotation {
+        job: &'a str,
+        level: &'a str,
+        title: Option<&'a str>,
+        message: Option<&'a str>,
+    },
+    RunCompleted {
+        conclusion: Option<&'a str>,
+        html_url: &'a str,
+    },
+}
+
+/// An event line: the event plus the run (and ref, when watching several)
+/// it belongs to.
+#[derive(Debug, Serialize)]
+struct EventLine<'a> {
+    run_id: u64,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    git_ref: Option<&'a str>,
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+/// Print one event as a line of JSON on stdout.
+fn emit(run_id: u64, label: Option<&str>, event: Event<'_>) {
+    let line = EventLine {
+        run_id,
+        git_ref: label,
+        event,
+    };
+    if let Ok(json) = serde_json::to_string(&line) {
+        let _ = writeln!(std::io::stdout().lock(), "{json}");
+    }
+}
+
+/// A progress group to watch runs in: hidden in `--json-events` mode, where
+/// stdout carries the events instead.
+pub fn progress_group(options: &WatchOptions) -> MultiProgress {
+    if options.events {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    }
+}
+
 /// Watch a workflow run, rendering job/step progress until completion.
-pub async fn watch_run(client: &Octocrab, owner: &str, repo: &str, run_id: u64) -> Result<Run> {
-    let multi = MultiProgress::new();
+pub async fn watch_run(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+    options: &WatchOptions,
+) -> Result<WatchedRun> {
+    watch_run_in(
+        client,
+        owner,
+        repo,
+        run_id,
+        &progress_group(options),
+        None,
+        options,
+    )
+    .await
+}
+
+/// Watch a workflow run inside a shared `MultiProgress` group.
+///
+/// Used to watch several runs at once; `label` (e.g. the git ref) prefixes
+/// each job and step line so the runs can be told apart.
+///
+/// With `WatchOptions::wait_for`, returns as soon as that job completes; the
+/// returned run (possibly still in progress) carries the job's conclusion.
+pub async fn watch_run_in(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+    multi: &MultiProgress,
+    label: Option<&str>,
+    options: &WatchOptions,
+) -> Result<WatchedRun> {
+    let tag = label.map_or_else(String::new, |l| format!("{} ", format!("[{l}]").dimmed()));
     // Per-job state: the progress bar and the last step number we already printed.
     let mut job_bars: HashMap<u64, (ProgressBar, u32)> = HashMap::new();
-    // Jobs whose annotations we have already fetched and printed.
-    let mut annotated: HashSet<u64> = HashSet::new();
+    // Jobs whose logs and annotations we have already fetched and printed.
+    let mut reported: HashSet<u64> = HashSet::new();
+    // Annotations fetched for reported jobs, kept for the caller.
+    let mut job_annotations: HashMap<u64, Vec<CheckRunAnnotation>> = HashMap::new();
+    // Set once annotations can't be fetched, so the failure is reported once.
+    let mut annotations_unavailable = false;
+    // Jobs we have already emitted a `job_started` event for.
+    let mut started: HashSet<u64> = HashSet::new();
+    // Live tally of job states above the per-job bars.
+    let status_bar = if options.events {
+        ProgressBar::hidden()
+    } else {
+        multi.add(ProgressBar::new_spinner())
+    };
+    status_bar.set_style(status_style());
+    status_bar.enable_steady_tick(Duration::from_secs(1));
+    // Single job-count line, replacing the per-job bars once `max_jobs` is exceeded.
+    let mut counts_bar: Option<ProgressBar> = None;
+    // Environments whose pending deployment we have already prompted or reported.
+    let mut reviewed: HashSet<u64> = HashSet::new();
+    // Run and job states at the last poll, and the current poll interval,
+    // for adaptive polling.
+    let mut last_snapshot = None;
+    let mut interval = POLL_INTERVAL;
+    // Last run and jobs responses, so unchanged polls are cheap 304s.
+    let mut cache = ResponseCache::default();
     let start = std::time::Instant::now();
 
     loop {
+        let polled = async {
+            let run = get_run(client, owner, repo, run_id, Some(&mut cache)).await?;
+            let jobs = get_run_jobs(client, owner, repo, run_id.into(), Some(&mut cache)).await?;
+            anyhow::Ok((run, jobs))
+        };
+        let (run, jobs) = match polled.await {
+            Ok(polled) => polled,
+            Err(err) => {
+                let Some(limit) = err
+                    .chain()
+                    .find_map(|e| e.downcast_ref::<SecondaryRateLimit>())
+                else {
+                    return Err(err);
+                };
+                let msg = format!(
+                    "GitHub secondary rate limit hit; retrying in {}s",
+                    limit.retry_after.as_secs()
+                );
+                if options.events {
+                    eprintln!("{msg}");
+                } else {
+                    let _ =
+                        multi.println(format!("{tag}{} {msg}", icons().warning.yellow().bold()));
+                }
+                tokio::time::sleep(limit.retry_after).await;
+                continue;
+            }
+        };
+
         if start.elapsed() > Duration::from_secs(MAX_WAIT) {
-            bail!("Timeout waiting for workflow completion (30 minutes)");
+            match options.on_timeout {
+                WaitTimeoutAction::Fail => {
+                    bail!("Timeout waiting for workflow completion (30 minutes)")
+                }
+                WaitTimeoutAction::Cancel => {
+                    cancel_run(client, owner, repo, run_id)
+                        .await
+                        .context("Timeout waiting for workflow completion (30 minutes)")?;
+                    bail!("Timeout waiting for workflow completion (30 minutes); cancelled the run")
+                }
+                WaitTimeoutAction::Continue => {
+                    status_bar.finish();
+                    for (bar, _) in job_bars.values() {
+                        bar.finish();
+                    }
+                    if let Some(bar) = &counts_bar {
+                        bar.finish();
+                    }
+                    return Ok(WatchedRun {
+                        run,
+                        jobs,
+                        timed_out: true,
+                        annotations: job_annotations,
+                    });
+                }
+            }
+        }
+
+        // Nothing changed since the last poll, which was already handled
+        if !cache.take_changed() {
+            if options.adaptive {
+                interval = (interval * 2).min(MAX_POLL_INTERVAL);
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            continue;
         }
 
-        let run = client.workflows(owner, repo).get(run_id.into()).await?;
+        if options.approve && run.status == "waiting" {
+            review_deployments(client, owner, repo, run_id, multi, &tag, &mut reviewed).await;
+        }
 
-        let jobs = get_run_jobs(client, owner, repo, run_id.into()).await?;
+        if counts_bar.is_none()
+            && !options.events
+            && let Some(max) = options.max_jobs
+            && jobs.len() > max
+        {
+            let _ = multi.println(format!(
+                "{}{} Run has {} jobs (more than --max-jobs {max}); showing counts only",
+                tag,
+                icons().warning.yellow().bold(),
+                jobs.len()
+            ));
+            // The counts line carries the same tally
+            status_bar.finish_and_clear();
+            multi.remove(&status_bar);
+            for (_, (bar, _)) in job_bars.drain() {
+                bar.finish_and_clear();
+                multi.remove(&bar);
+            }
+            let bar = multi.add(ProgressBar::new_spinner());
+            bar.set_style(spinner_style());
+            bar.enable_steady_tick(Duration::from_millis(TICK_INTERVAL));
+            counts_bar = Some(bar);
+        }
+        if let Some(bar) = &counts_bar {
+            bar.set_message(format!("{}{}", tag, format_counts(&jobs)));
+        } else {
+            status_bar.set_message(format!("{tag}{}", format_status(&run, &jobs)));
+        }
 
-        for job in &jobs {
+        // In condensed mode no per-job state is kept or shown.
+        let shown: &[Job] = if counts_bar.is_some() { &[] } else { &jobs };
+        for job in shown {
             let (bar, last_step) = job_bars.entry(job.id).or_insert_with(|| {
+                if options.events {
+                    return (ProgressBar::hidden(), 0);
+                }
                 let b = multi.add(ProgressBar::new_spinner());
-                b.set_style(
-                    ProgressStyle::default_spinner()
-                        .template("{spinner:.cyan} {msg}")
-                        .unwrap(),
-                );
+                // `wide_msg` truncates to the terminal width on every draw, so
+                // long job lines never wrap and corrupt the display on resize.
+                b.set_style(spinner_style());
                 b.enable_steady_tick(Duration::from_millis(TICK_INTERVAL));
                 (b, 0)
             });
@@ -58,59 +353,442 @@ pub async fn watch_run(client: &Octocrab, owner: &str, repo: &str, run_id: u64)
                 .iter()
                 .filter(|s| s.number > *last_step && s.status == JobStatus::Completed)
                 .collect();
+            if options.events
+                && matches!(job.status, JobStatus::InProgress | JobStatus::Completed)
+                && started.insert(job.id)
+            {
+                emit(run_id, label, Event::JobStarted { job: &job.name });
+            }
             for step in new_steps {
+                *last_step = step.number;
+                let failed = matches!(
+                    step.conclusion,
+                    Some(JobConclusion::Failure | JobConclusion::TimedOut)
+                );
+                match options.steps {
+                    StepFilter::All => {}
+                    StepFilter::Failed if failed => {}
+                    StepFilter::Failed | StepFilter::None => continue,
+                }
+                if options.events {
+                    emit(
+                        run_id,
+                        label,
+                        Event::StepCompleted {
+                            job: &job.name,
+                            step: &step.name,
+                            conclusion: step.conclusion.as_ref(),
+                        },
+                    );
+                    continue;
+                }
                 let icon = match &step.conclusion {
-                    Some(JobConclusion::Success) => "  ✓".green().to_string(),
-                    Some(JobConclusion::Failure) => "  ✗".red().to_string(),
-                    Some(JobConclusion::Skipped) => "  ○".dimmed().to_string(),
-                    _ => "  ?".dimmed().to_string(),
+                    Some(JobConclusion::Success) => icons().success.green(),
+                    Some(JobConclusion::Failure) => icons().failure.red(),
+                    Some(JobConclusion::Skipped) => icons().neutral.dimmed(),
+                    _ => icons().unknown.dimmed(),
                 };
-                let _ = multi.println(format!("{} {}", icon, step.name));
-                *last_step = step.number;
+                let _ = multi.println(format!("{}  {} {}", tag, icon, step.name));
             }
 
             // Update the job's spinner message.
-            bar.set_message(format_job_message(job));
+            bar.set_message(format!("{}{}", tag, format_job_message(job)));
 
-            if job.status == JobStatus::Completed {
+            if job.status == JobStatus::Completed && reported.insert(job.id) {
                 bar.finish();
+                if options.events {
+                    emit(
+                        run_id,
+                        label,
+                        Event::JobCompleted {
+                            job: &job.name,
+                            conclusion: job.conclusion.as_ref(),
+                            duration_seconds: job.duration_seconds(),
+                        },
+                    );
+                }
 
-                // Fetch and print annotations once per job.
-                if let Some(check_run_id) = check_run_id_from_url(&job.check_run_url)
-                    && annotated.insert(job.id)
-                {
-                    let annotations = get_annotations(client, owner, repo, check_run_id).await?;
-                    for ann in &annotations {
+                // Fetch and print logs (if requested) and annotations once per job.
+                if options.logs && !options.events {
+                    match get_job_logs(client, owner, repo, job.id).await {
+                        Ok(log) => {
+                            for line in format_log(&log) {
+                                let _ = multi.println(format!("{tag}{line}"));
+                            }
+                        }
+                        Err(err) => {
+                            let msg = format!("    logs unavailable: {err:#}");
+                            let _ = multi.println(format!("{tag}{}", msg.dimmed()));
+                        }
+                    }
+                }
+                // Annotations are a nicety: failing to get them mustn't fail the watch
+                let annotations = match check_run_id_from_url(&job.check_run_url) {
+                    _ if annotations_unavailable => None,
+                    Some(check_run_id) => {
+                        match get_annotations(client, owner, repo, check_run_id).await {
+                            Ok(annotations) => Some(annotations),
+                            Err(err) => {
+                                annotations_unavailable = true;
+                                let reason = if is_forbidden(&err) {
+                                    "insufficient permissions".to_string()
+                                } else {
+                                    err.root_cause().to_string()
+                                };
+                                note(
+                                    multi,
+                                    &tag,
+                                    options,
+                                    &format!("annotations unavailable: {reason}"),
+                                );
+                                None
+                            }
+                        }
+                    }
+                    None => {
+                        let msg = format!(
+                            "annotations unavailable for '{}': unrecognised check run URL",
+                            job.name
+                        );
+                        note(multi, &tag, options, &msg);
+                        None
+                    }
+                };
+                if let Some(annotations) = annotations {
+                    let shown = annotations.iter().filter(|ann| {
+                        let level = ann.annotation_level.as_deref().unwrap_or("notice");
+                        options.annotations.shows(level)
+                    });
+                    for ann in shown {
+                        if options.events {
+                            emit(
+                                run_id,
+                                label,
+                                Event::Annotation {
+                                    job: &job.name,
+                                    level: ann.annotation_level.as_deref().unwrap_or("notice"),
+                                    title: ann.title.as_deref(),
+                                    message: ann.message.as_deref(),
+                                },
+                            );
+                            continue;
+                        }
                         let (prefix, msg) = format_annotation(ann);
-                        let _ = multi.println(format!("{prefix} {msg}"));
+                        let _ = multi.println(format!("{tag}{prefix} {msg}"));
                     }
+                    job_annotations.insert(job.id, annotations);
                 }
             }
         }
 
+        if let Some(target) = &options.wait_for
+            && let Some(job) = jobs
+                .iter()
+                .find(|j| &j.name == target && j.status == JobStatus::Completed)
+        {
+            status_bar.finish();
+            for (bar, _) in job_bars.values() {
+                bar.finish();
+            }
+            let mut run = run;
+            run.conclusion = Some(
+                job.conclusion
+                    .as_ref()
+                    .map_or("unknown", JobConclusion::as_str)
+                    .to_string(),
+            );
+            if !options.events {
+                let _ = multi.println("");
+            }
+            return Ok(WatchedRun {
+                run,
+                jobs,
+                timed_out: false,
+                annotations: job_annotations,
+            });
+        }
+
+        if options.fail_fast
+            && run.status != "completed"
+            && let Some(job) = jobs.iter().find(|j| {
+                j.status == JobStatus::Completed && j.conclusion == Some(JobConclusion::Failure)
+            })
+        {
+            status_bar.finish();
+            for (bar, _) in job_bars.values() {
+                bar.finish();
+            }
+            if let Some(bar) = &counts_bar {
+                bar.finish();
+            }
+            if !options.events {
+                multi.suspend(|| {
+                    status_line("");
+                    status_line(&format!(
+                        "{}{} Job '{}' failed; not waiting for the rest of the run",
+                        tag,
+                        icons().failure.red().bold(),
+                        job.name
+                    ));
+                });
+            }
+            let mut run = run;
+            run.conclusion = Some(JobConclusion::Failure.as_str().to_string());
+            return Ok(WatchedRun {
+                run,
+                jobs,
+                timed_out: false,
+                annotations: job_annotations,
+            });
+        }
+
         if run.status == "completed" {
             // Ensure all bars are finished (handles edge case where jobs
             // weren't fetched on the final tick).
+            status_bar.finish();
             for (bar, _) in job_bars.values() {
                 bar.finish();
             }
-            let _ = multi.println("");
-            return Ok(run);
+            if let Some(bar) = &counts_bar {
+                bar.finish();
+            }
+            if options.events {
+                emit(
+                    run_id,
+                    label,
+                    Event::RunCompleted {
+                        conclusion: run.conclusion.as_deref(),
+                        html_url: run.html_url.as_str(),
+                    },
+                );
+            } else {
+                // Printed around the progress group rather than through it,
+                // which drops its lines when stderr isn't a terminal
+                let summary = if jobs.is_empty() {
+                    vec![format!(
+                        "{}{} {}",
+                        tag,
+                        icons().warning.yellow().bold(),
+                        "Run completed without starting any jobs"
+                    )]
+                } else if counts_bar.is_some() {
+                    vec![format!("{tag}{}", format_totals(&jobs))]
+                } else {
+                    format_summary(&jobs)
+                        .into_iter()
+                        .map(|line| format!("{tag}{line}"))
+                        .collect()
+                };
+                multi.suspend(|| {
+                    status_line("");
+                    for line in &summary {
+                        status_line(line);
+                    }
+                    status_line("");
+                });
+            }
+            if let Some(target) = &options.wait_for {
+                bail!("Job '{target}' did not run before the workflow completed");
+            }
+            return Ok(WatchedRun {
+                run,
+                jobs,
+                timed_out: false,
+                annotations: job_annotations,
+            });
+        }
+
+        if options.adaptive {
+            let snapshot = snapshot(&run, &jobs);
+            interval = if last_snapshot.as_ref() == Some(&snapshot) {
+                (interval * 2).min(MAX_POLL_INTERVAL)
+            } else {
+                POLL_INTERVAL
+            };
+            last_snapshot = Some(snapshot);
         }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
 
-        tokio::time::sleep(Duration::from_secs(POLL_INTERVAL)).await;
+/// Style of the status line: the tally, then the time spent watching as `m:ss`.
+fn status_style() -> ProgressStyle {
+    ProgressStyle::with_template("{wide_msg} {watched:.dim}")
+        .unwrap()
+        .with_key(
+            "watched",
+            |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+                let _ = write!(w, "({})", format_seconds(state.elapsed().as_secs() as i64));
+            },
+        )
+}
+
+/// The status line's tally of a run's jobs, e.g.
+/// `Run #123: 2 running · 3 done · 1 queued · 0 failed`.
+fn format_status(run: &Run, jobs: &[Job]) -> String {
+    let sep = &icons().separator;
+    let running = jobs
+        .iter()
+        .filter(|j| j.status == JobStatus::InProgress)
+        .count();
+    let done = jobs
+        .iter()
+        .filter(|j| j.status == JobStatus::Completed)
+        .count();
+    let failed = jobs
+        .iter()
+        .filter(|j| {
+            matches!(
+                j.conclusion,
+                Some(JobConclusion::Failure | JobConclusion::TimedOut)
+            )
+        })
+        .count();
+    let failed_text = format!("{failed} failed");
+    let failed_text = if failed > 0 {
+        failed_text.red().bold()
+    } else {
+        failed_text.normal()
+    };
+    format!(
+        "{}: {} {sep} {} {sep} {} {sep} {}",
+        format!("Run #{}", run.run_number).bold(),
+        format!("{running} running").yellow(),
+        format!("{done} done").green(),
+        format!("{} queued", jobs.len() - running - done).dimmed(),
+        failed_text
+    )
+}
+
+/// Print a dimmed note about a problem that doesn't stop the watch; to stderr
+/// in `--json-events` mode.
+fn note(multi: &MultiProgress, tag: &str, options: &WatchOptions, msg: &str) {
+    if options.events {
+        eprintln!("{msg}");
+    } else {
+        let _ = multi.println(format!("{tag}{}", format!("    {msg}").dimmed()));
+    }
+}
+
+/// Whether an API error is GitHub refusing the token (403), e.g. for lacking
+/// the checks permission.
+fn is_forbidden(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        matches!(
+            e.downcast_ref::<octocrab::Error>(),
+            Some(octocrab::Error::GitHub { source, .. })
+                if source.status_code == http::StatusCode::FORBIDDEN
+        )
+    })
+}
+
+/// A job's id, status, conclusion, and number of completed steps.
+type JobState = (u64, JobStatus, Option<JobConclusion>, usize);
+
+/// The parts of a run and its jobs whose change means the run is active:
+/// statuses, conclusions, and completed step counts.
+fn snapshot(run: &Run, jobs: &[Job]) -> (String, Vec<JobState>) {
+    let jobs = jobs
+        .iter()
+        .map(|job| {
+            let steps_done = job
+                .steps
+                .iter()
+                .filter(|s| s.status == JobStatus::Completed)
+                .count();
+            (
+                job.id,
+                job.status.clone(),
+                job.conclusion.clone(),
+                steps_done,
+            )
+        })
+        .collect();
+    (run.status.clone(), jobs)
+}
+
+/// Offer to approve each newly pending deployment the current user may
+/// approve, and note the ones waiting on someone else.  Failures are printed
+/// as warnings so watching continues.
+async fn review_deployments(
+    client: &Octocrab,
+    owner: &str,
+    repo: &str,
+    run_id: u64,
+    multi: &MultiProgress,
+    tag: &str,
+    reviewed: &mut HashSet<u64>,
+) {
+    let warn = |msg: String| {
+        let _ = multi.println(format!("{tag}{} {msg}", icons().warning.yellow().bold()));
+    };
+    let pending = match get_pending_deployments(client, owner, repo, run_id).await {
+        Ok(pending) => pending,
+        Err(err) => {
+            warn(format!(
+                "Could not check pending deployments: {}",
+                err.root_cause()
+            ));
+            return;
+        }
+    };
+
+    let mut approved = Vec::new();
+    for deployment in pending {
+        let env = deployment.environment;
+        if !reviewed.insert(env.id) {
+            continue;
+        }
+        if !deployment.current_user_can_approve {
+            let _ = multi.println(format!(
+                "{tag}{} Deployment to '{}' is waiting for a reviewer",
+                icons().info.blue().bold(),
+                env.name
+            ));
+            continue;
+        }
+        // inquire blocks until answered, so keep it off the runtime's threads
+        let prompt = format!("Approve deployment to {}?", env.name);
+        let progress = multi.clone();
+        let answer = tokio::task::spawn_blocking(move || {
+            progress.suspend(|| Confirm::new(&prompt).with_default(false).prompt())
+        })
+        .await;
+        if matches!(answer, Ok(Ok(true))) {
+            approved.push((env.id, env.name));
+        }
+    }
+    if approved.is_empty() {
+        return;
+    }
+
+    let ids: Vec<u64> = approved.iter().map(|(id, _)| *id).collect();
+    let names: Vec<&str> = approved.iter().map(|(_, name)| name.as_str()).collect();
+    match approve_deployments(client, owner, repo, run_id, &ids).await {
+        Ok(()) => {
+            let _ = multi.println(format!(
+                "{tag}{} Approved deployment to {}",
+                icons().success.green().bold(),
+                names.join(", ")
+            ));
+        }
+        Err(err) => warn(format!(
+            "Could not approve deployment to {}: {}",
+            names.join(", "),
+            err.root_cause()
+        )),
     }
 }
 
 /// Build the display message for a single job spinner.
 fn format_job_message(job: &Job) -> String {
     let icon = match (&job.status, &job.conclusion) {
-        (JobStatus::Completed, Some(JobConclusion::Success)) => "✓".green().bold().to_string(),
-        (JobStatus::Completed, Some(JobConclusion::Failure)) => "✗".red().bold().to_string(),
-        (JobStatus::Completed, Some(JobConclusion::Cancelled)) => "○".yellow().to_string(),
-        (JobStatus::Completed, _) => "○".dimmed().to_string(),
-        (JobStatus::InProgress, _) => "●".cyan().to_string(),
-        _ => "○".dimmed().to_string(), // queued / waiting / pending
+        (JobStatus::Completed, Some(JobConclusion::Success)) => icons().success.green().bold(),
+        (JobStatus::Completed, Some(JobConclusion::Failure)) => icons().failure.red().bold(),
+        (JobStatus::Completed, Some(JobConclusion::Cancelled)) => icons().neutral.yellow(),
+        (JobStatus::Completed, _) => icons().neutral.dimmed(),
+        (JobStatus::InProgress, _) => icons().running.cyan(),
+        _ => icons().neutral.dimmed(), // queued / waiting / pending
     };
 
     let status_suffix = match &job.status {
@@ -123,7 +801,7 @@ fn format_job_message(job: &Job) -> String {
                 .find(|s| s.status == JobStatus::InProgress)
                 .map_or_else(
                     || " (running)".dimmed().to_string(),
-                    |s| format!(" → {}", s.name.dimmed()),
+                    |s| format!(" {} {}", icons().info, s.name.dimmed()),
                 )
         }
         JobStatus::Completed => format_duration(job),
@@ -133,6 +811,52 @@ fn format_job_message(job: &Job) -> String {
     format!("{} {}{}", icon, job.name.bold(), status_suffix)
 }
 
+/// Build the condensed status line: how many jobs are in each state.
+fn format_counts(jobs: &[Job]) -> String {
+    let sep = &icons().separator;
+    let count = |status: JobStatus| jobs.iter().filter(|j| j.status == status).count();
+    let failed = jobs
+        .iter()
+        .filter(|j| {
+            matches!(
+                j.conclusion,
+                Some(JobConclusion::Failure | JobConclusion::TimedOut)
+            )
+        })
+        .count();
+    format!(
+        "{} jobs: {} completed ({} failed) {sep} {} running {sep} {} queued",
+        jobs.len().to_string().bold(),
+        count(JobStatus::Completed),
+        failed,
+        count(JobStatus::InProgress),
+        jobs.len() - count(JobStatus::Completed) - count(JobStatus::InProgress)
+    )
+}
+
+/// Format a job log for display beneath the job.
+///
+/// Strips the leading timestamp from each line, shows `##[group]` markers
+/// (one per step) as headings, and drops `##[endgroup]` markers.
+fn format_log(log: &str) -> Vec<String> {
+    log.lines()
+        .filter_map(|line| {
+            // Lines look like "2024-01-01T12:00:00.0000000Z message"
+            let text = match line.split_once(' ') {
+                Some((stamp, rest)) if stamp.ends_with('Z') && stamp.contains('T') => rest,
+                _ => line,
+            };
+            if text.starts_with("##[endgroup]") {
+                None
+            } else if let Some(heading) = text.strip_prefix("##[group]") {
+                Some(format!("    {}", heading.bold()))
+            } else {
+                Some(format!("    {} {}", icons().gutter.dimmed(), text))
+            }
+        })
+        .collect()
+}
+
 /// Format a single annotation for terminal output.
 ///
 /// Returns (colored prefix, message body).  The prefix reflects the annotation
@@ -140,10 +864,11 @@ fn format_job_message(job: &Job) -> String {
 fn format_annotation(ann: &CheckRunAnnotation) -> (String, String) {
     let level = ann.annotation_level.as_deref().unwrap_or("notice");
     let prefix = match level {
-        "failure" => "    ✗".red().bold().to_string(),
-        "warning" => "    !".yellow().bold().to_string(),
-        _ => "    →".blue().bold().to_string(), // notice
+        "failure" => icons().failure.red().bold(),
+        "warning" => icons().warning.yellow().bold(),
+        _ => icons().info.blue().bold(), // notice
     };
+    let prefix = format!("    {prefix}");
 
     let title = ann.title.as_deref().unwrap_or("");
     let message = ann.message.as_deref().unwrap_or("");
@@ -158,13 +883,178 @@ fn format_annotation(ann: &CheckRunAnnotation) -> (String, String) {
 
 /// Format the duration a completed job took, or empty string if timestamps missing.
 fn format_duration(job: &Job) -> String {
-    match (&job.started_at, &job.completed_at) {
-        (Some(start), Some(end)) => {
-            let secs = (*end - *start).num_seconds().max(0);
-            format!(" ({}:{:02})", secs / 60, secs % 60)
-                .dimmed()
-                .to_string()
+    job.duration_seconds().map_or_else(String::new, |secs| {
+        format!(" ({})", format_seconds(secs)).dimmed().to_string()
+    })
+}
+
+/// Format seconds as `m:ss`.
+pub fn format_seconds(secs: i64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Build the final job summary table: one aligned row per job (status icon,
+/// name, right-aligned duration, conclusion) and a footer with totals.
+fn format_summary(jobs: &[Job]) -> Vec<String> {
+    let durations: Vec<String> = jobs
+        .iter()
+        .map(|job| {
+            job.duration_seconds()
+                .map_or_else(|| "-".to_string(), format_seconds)
+        })
+        .collect();
+    let name_width = jobs
+        .iter()
+        .map(|j| j.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let duration_width = durations.iter().map(String::len).max().unwrap_or(0);
+
+    let mut lines = Vec::with_capacity(jobs.len() + 2);
+    for (job, duration) in jobs.iter().zip(&durations) {
+        let (icon, conclusion) = match &job.conclusion {
+            Some(JobConclusion::Success) => (icons().success.green().bold(), "success".green()),
+            Some(JobConclusion::Failure) => (icons().failure.red().bold(), "failure".red()),
+            Some(JobConclusion::Cancelled) => (icons().neutral.yellow(), "cancelled".yellow()),
+            Some(JobConclusion::TimedOut) => (icons().failure.red().bold(), "timed out".red()),
+            Some(JobConclusion::Skipped) => (icons().neutral.dimmed(), "skipped".dimmed()),
+            Some(other) => (
+                icons().neutral.dimmed(),
+                format!("{other:?}").to_lowercase().dimmed(),
+            ),
+            None => (icons().neutral.dimmed(), "incomplete".dimmed()),
+        };
+        lines.push(format!(
+            "{icon} {:<name_width$}  {}  {conclusion}",
+            job.name,
+            format!("{duration:>duration_width$}").dimmed(),
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format_totals(jobs));
+    lines
+}
+
+/// Build the summary footer: succeeded and failed job counts, and total time.
+fn format_totals(jobs: &[Job]) -> String {
+    let succeeded = jobs
+        .iter()
+        .filter(|j| j.conclusion == Some(JobConclusion::Success))
+        .count();
+    let failed = jobs
+        .iter()
+        .filter(|j| {
+            matches!(
+                j.conclusion,
+                Some(JobConclusion::Failure | JobConclusion::TimedOut)
+            )
+        })
+        .count();
+    let start = jobs.iter().filter_map(|j| j.started_at).min();
+    let end = jobs.iter().filter_map(|j| j.completed_at).max();
+    let total = match (start, end) {
+        (Some(start), Some(end)) => format_seconds((end - start).num_seconds().max(0)),
+        _ => "-".to_string(),
+    };
+
+    let failed_text = format!("{failed} failed");
+    let failed_text = if failed > 0 {
+        failed_text.red()
+    } else {
+        failed_text.normal()
+    };
+    let sep = &icons().separator;
+    format!(
+        "{} {sep} {} {sep} total {}",
+        format!("{succeeded} succeeded").green(),
+        failed_text,
+        total.bold()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve a completed run with no jobs on a local port, returning its base URI.
+    async fn serve_completed_run() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let url = "https://api.github.com/repos/o/r/actions/runs/1";
+        let run = serde_json::json!({
+            "id": 1,
+            "workflow_id": 7,
+            "node_id": "WFR_1",
+            "name": "Deploy",
+            "head_branch": "main",
+            "head_sha": "abc123",
+            "run_number": 1,
+            "event": "workflow_dispatch",
+            "status": "completed",
+            "conclusion": "success",
+            "created_at": "2026-01-01T00:00:00Z",
+            "updated_at": "2026-01-01T00:01:00Z",
+            "url": url,
+            "html_url": "https://github.com/o/r/actions/runs/1",
+            "jobs_url": format!("{url}/jobs"),
+            "logs_url": format!("{url}/logs"),
+            "check_suite_url": "https://api.github.com/repos/o/r/check-suites/1",
+            "artifacts_url": format!("{url}/artifacts"),
+            "cancel_url": format!("{url}/cancel"),
+            "rerun_url": format!("{url}/rerun"),
+            "workflow_url": "https://api.github.com/repos/o/r/actions/workflows/7",
+            "head_commit": {
+                "id": "abc123",
+                "tree_id": "def456",
+                "message": "Ship it",
+                "timestamp": "2026-01-01T00:00:00Z",
+                "author": { "name": "Dev" },
+                "committer": { "name": "Dev" },
+            },
+            "repository": {
+                "id": 3,
+                "name": "r",
+                "url": "https://api.github.com/repos/o/r",
+            },
+        })
+        .to_string();
+        let jobs = serde_json::json!({ "total_count": 0, "jobs": [] }).to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let body = if path.contains("/jobs") { &jobs } else { &run };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn wait_for_a_job_that_never_ran_fails_in_both_modes() {
+        let base = serve_completed_run().await;
+        let client = Octocrab::builder().base_uri(base).unwrap().build().unwrap();
+        for events in [false, true] {
+            let options = WatchOptions {
+                events,
+                wait_for: Some("missing".to_string()),
+                ..Default::default()
+            };
+            let err = watch_run(&client, "o", "r", 1, &options).await.unwrap_err();
+            assert!(
+                err.to_string().contains("Job 'missing' did not run"),
+                "events: {events}: {err}"
+            );
         }
-        _ => String::new(),
     }
 }